@@ -0,0 +1,49 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::ecs::Component;
+
+/// Opaque id of an asset handle kept alive by host on behalf of mods.
+///
+/// It can be used as a component when spawning entities, which host replaces by the typed
+/// handle, like `Handle<Mesh>`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetHandle {
+    pub id: u64,
+}
+
+impl AssetHandle {
+    pub fn component(self) -> Component {
+        Component::from(Box::new(self) as Box<dyn Reflect>)
+    }
+}
+
+/// Loads an asset using host `AssetServer`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct LoadAsset {
+    /// Path relative to the mod file, inside host assets folder.
+    pub path: String,
+    /// Short type name of the asset, like `Mesh`, `Image` or `StandardMaterial`.
+    pub asset_type: String,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetLoadState {
+    #[default]
+    NotLoaded,
+    Loading,
+    Loaded,
+    Failed,
+    Unloaded,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct GetLoadState {
+    pub handle: AssetHandle,
+}
+
+/// Releases the mod reference to the asset. Host drops its handle once no mod references it,
+/// but entities using the asset keep it alive.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct ReleaseAsset {
+    pub handle: AssetHandle,
+}
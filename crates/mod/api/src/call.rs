@@ -0,0 +1,20 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+/// Calls a function exported by another mod with `#[wabi::export]`.
+///
+/// `args` holds the argument already serialized by the calling mod, so host forwards it as is
+/// and only mods need to know its type. The response is the serialized return value, or an
+/// [`ActionError`](crate::error::ActionError).
+///
+/// Re-entrancy rules:
+/// - A mod can't call itself nor any mod which is already running, like a mod which is
+///   calling it, directly or through other mods. Those calls fails with `ReentrantCall`;
+/// - Exported functions run with their own context, so actions sent by them are attributed
+///   to the exporting mod;
+/// - Exported functions may call other mods, as long as the rules above are followed.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct CallMod {
+    pub module: String,
+    pub function: String,
+    pub args: Vec<u8>,
+}
@@ -0,0 +1,87 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::Action;
+
+/// Permission to use a group of actions. Mods request capabilities on their manifest and host
+/// decides which of those are granted.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Read entities and their components, including hierarchy.
+    #[default]
+    Query,
+    /// Insert, remove and change components, including hierarchy.
+    Write,
+    /// Spawn and despawn entities.
+    Spawn,
+    /// Call functions exported by other mods.
+    Call,
+    Storage,
+    Input,
+    /// Load, create and change assets.
+    Assets,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 7] = [
+        Capability::Query,
+        Capability::Write,
+        Capability::Spawn,
+        Capability::Call,
+        Capability::Storage,
+        Capability::Input,
+        Capability::Assets,
+    ];
+
+    /// Name used on manifests.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Query => "query",
+            Capability::Write => "write",
+            Capability::Spawn => "spawn",
+            Capability::Call => "call",
+            Capability::Storage => "storage",
+            Capability::Input => "input",
+            Capability::Assets => "assets",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Capability> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|capability| capability.name() == name)
+    }
+}
+
+impl Action {
+    /// Capability needed to use this action, if any. [`Action::COMMANDS`] has none, since each
+    /// command is checked on its own.
+    pub fn capability(self) -> Option<Capability> {
+        match self {
+            Action::QUERY | Action::GET_RELATIVES => Some(Capability::Query),
+            Action::SET_PARENT => Some(Capability::Write),
+            Action::SPAWN => Some(Capability::Spawn),
+            Action::CALL_MOD => Some(Capability::Call),
+            Action::STORAGE_GET | Action::STORAGE_SET | Action::STORAGE_REMOVE => {
+                Some(Capability::Storage)
+            }
+            Action::INPUT => Some(Capability::Input),
+            Action::LOAD_ASSET
+            | Action::GET_LOAD_STATE
+            | Action::RELEASE_ASSET
+            | Action::CREATE_MESH
+            | Action::UPDATE_MESH
+            | Action::CREATE_MATERIAL
+            | Action::PATCH_MATERIAL
+            | Action::CREATE_IMAGE
+            | Action::UPDATE_IMAGE => Some(Capability::Assets),
+            Action::LOG
+            | Action::PANIC
+            | Action::REGISTER_SYSTEM
+            | Action::RANDOM_SEED
+            | Action::COMMANDS
+            | Action::TEST
+            | Action::INVALID => None,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::{
+    capability::Capability,
+    ecs::{Component, Entity},
+    query::FieldPath,
+    value::Value,
+};
+
+/// Entity targeted by a [`Command`]. Entities spawned by the same batch don't exist yet when
+/// commands are recorded, so they are referenced by the order they were spawned, starting at 0.
+#[derive(Reflect, FromReflect, Debug, Clone, Copy)]
+pub enum CommandEntity {
+    Entity(Entity),
+    Pending(u32),
+}
+
+impl From<Entity> for CommandEntity {
+    fn from(entity: Entity) -> Self {
+        CommandEntity::Entity(entity)
+    }
+}
+
+#[derive(Reflect, FromReflect, Debug, Clone)]
+pub enum Command {
+    Spawn {
+        components: Vec<Component>,
+        parent: Option<CommandEntity>,
+    },
+    Insert {
+        entity: CommandEntity,
+        components: Vec<Component>,
+    },
+    /// Removes components by name, resolved like on [`crate::query::Query`].
+    Remove {
+        entity: CommandEntity,
+        components: Vec<String>,
+    },
+    /// Despawns the entity along with all its descendants.
+    Despawn { entity: CommandEntity },
+    /// Sets a single field of a component. Value type must match the field type.
+    Set {
+        entity: CommandEntity,
+        field: FieldPath,
+        value: Value,
+    },
+}
+
+impl Command {
+    pub fn capability(&self) -> Capability {
+        match self {
+            Command::Spawn { .. } | Command::Despawn { .. } => Capability::Spawn,
+            Command::Insert { .. } | Command::Remove { .. } | Command::Set { .. } => {
+                Capability::Write
+            }
+        }
+    }
+}
+
+/// Commands recorded by a mod, applied by host in order. A failed command doesn't stop the
+/// following ones, but commands targeting an entity it should have spawned also fail.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct CommandBatch {
+    pub commands: Vec<Command>,
+}
@@ -4,6 +4,12 @@ use bevy_reflect::{FromReflect, Reflect};
 
 use crate::reflect_proxy;
 
+/// Host entity. It can be kept across frames, but once despawned, actions using it fail with
+/// [`crate::error::ActionError::StaleEntity`], even if its index is reused by another entity.
+///
+/// Host translates entities only when they are a whole value, like a fetched `Entity`, `Parent` or
+/// `Children` component. Entities nested inside other components, or inside components sent by
+/// mods, aren't translated, so they aren't valid on the other side.
 #[derive(Reflect, FromReflect, Default, Debug, Clone, Copy)]
 pub struct Entity {
     pub id: u32,
@@ -11,3 +17,17 @@ pub struct Entity {
 }
 
 reflect_proxy::impl_type!(Component);
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct EntityList {
+    pub entities: Vec<Entity>,
+}
+
+/// Spawns a new entity with the given components, optionally as a child of `parent`.
+/// Use `parent` instead of a `Parent` component, since entities inside components aren't
+/// translated to host entities.
+#[derive(Reflect, FromReflect, Default, Debug)]
+pub struct Spawn {
+    pub components: Vec<Component>,
+    pub parent: Option<Entity>,
+}
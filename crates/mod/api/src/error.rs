@@ -0,0 +1,165 @@
+use std::fmt::Display;
+
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::{capability::Capability, ecs::Entity};
+
+/// Error sent back by host when an action can't be processed.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub enum ActionError {
+    #[default]
+    Unknown,
+    /// Response received from host couldn't be decoded on the expected type.
+    InvalidResponse(String),
+    /// Action sent by mod couldn't be decoded by host, with the reason.
+    InvalidRequest(String),
+    EntityNotFound(Entity),
+    /// Entity was despawned, so its index may be used by another entity now.
+    StaleEntity {
+        entity: Entity,
+        current_generation: u32,
+    },
+    ComponentNotFound(String),
+    /// More than one component matches the given name.
+    AmbiguousComponent {
+        name: String,
+        candidates: Vec<String>,
+    },
+    ComponentNotReflected(String),
+    InvalidPath {
+        component: String,
+        path: String,
+        reason: String,
+    },
+    InvalidPredicate {
+        component: String,
+        path: String,
+        reason: String,
+    },
+    InvalidHierarchy(String),
+    ModuleNotFound(String),
+    ExportNotFound {
+        module: String,
+        function: String,
+    },
+    /// Exported function returned an error, panicked or trapped.
+    ExportFailed {
+        function: String,
+        reason: String,
+    },
+    /// Called module is already running, so it can't be called again.
+    ReentrantCall(String),
+    InvalidStorageKey(String),
+    /// Writing the value would make mod storage bigger than its quota, in bytes.
+    StorageQuotaExceeded {
+        size: u64,
+        quota: u64,
+    },
+    /// Storage failed to read or write on host, like when there is no file system.
+    Storage(String),
+    AssetNotFound(u64),
+    /// Asset type wasn't registered on host as available to mods.
+    UnknownAssetType(String),
+    /// Asset paths must be relative and can't leave mod directory.
+    InvalidAssetPath(String),
+    /// Asset exists, but it isn't of the expected type.
+    MismatchedAssetType {
+        id: u64,
+        expected: String,
+    },
+    InvalidMesh(String),
+    InvalidImage(String),
+    /// Command targets an entity which wasn't spawned by its batch, or was despawned since.
+    PendingEntityNotFound(u32),
+    /// Mod wasn't granted the capability needed by the action.
+    PermissionDenied(Capability),
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionError::Unknown => write!(f, "Unknown error"),
+            ActionError::InvalidResponse(type_path) => {
+                write!(f, "Invalid response, expected: {}", type_path)
+            }
+            ActionError::InvalidRequest(reason) => write!(f, "Invalid request: {}", reason),
+            ActionError::EntityNotFound(entity) => {
+                write!(f, "Entity not found: {}v{}", entity.id, entity.generation)
+            }
+            ActionError::StaleEntity {
+                entity,
+                current_generation,
+            } => write!(
+                f,
+                "Entity {}v{} is stale, it was despawned and its index is now on generation {}",
+                entity.id, entity.generation, current_generation
+            ),
+            ActionError::ComponentNotFound(name) => write!(f, "Component not found: {}", name),
+            ActionError::AmbiguousComponent { name, candidates } => write!(
+                f,
+                "Ambiguous component name {}, candidates: {}",
+                name,
+                candidates.join(", ")
+            ),
+            ActionError::ComponentNotReflected(name) => {
+                write!(f, "Component isn't registered as reflect: {}", name)
+            }
+            ActionError::InvalidPath {
+                component,
+                path,
+                reason,
+            } => write!(f, "Invalid path {} on {}: {}", path, component, reason),
+            ActionError::InvalidPredicate {
+                component,
+                path,
+                reason,
+            } => write!(
+                f,
+                "Invalid predicate at {} on {}: {}",
+                path, component, reason
+            ),
+            ActionError::InvalidHierarchy(reason) => write!(f, "Invalid hierarchy: {}", reason),
+            ActionError::ModuleNotFound(name) => write!(f, "Module not found: {}", name),
+            ActionError::ExportNotFound { module, function } => {
+                write!(f, "Module {} doesn't export function {}", module, function)
+            }
+            ActionError::ExportFailed { function, reason } => {
+                write!(f, "Exported function {} failed: {}", function, reason)
+            }
+            ActionError::ReentrantCall(name) => {
+                write!(f, "Module {} is already running and can't be called", name)
+            }
+            ActionError::InvalidStorageKey(key) => write!(f, "Invalid storage key: {}", key),
+            ActionError::StorageQuotaExceeded { size, quota } => write!(
+                f,
+                "Storage quota exceeded: {} bytes required, quota is {} bytes",
+                size, quota
+            ),
+            ActionError::Storage(reason) => write!(f, "Storage failed: {}", reason),
+            ActionError::AssetNotFound(id) => write!(f, "Asset not found: {}", id),
+            ActionError::UnknownAssetType(name) => write!(f, "Unknown asset type: {}", name),
+            ActionError::InvalidAssetPath(path) => write!(f, "Invalid asset path: {}", path),
+            ActionError::MismatchedAssetType { id, expected } => {
+                write!(f, "Asset {} isn't of type {}", id, expected)
+            }
+            ActionError::InvalidMesh(reason) => write!(f, "Invalid mesh: {}", reason),
+            ActionError::InvalidImage(reason) => write!(f, "Invalid image: {}", reason),
+            ActionError::PendingEntityNotFound(index) => {
+                write!(
+                    f,
+                    "Entity {} wasn't spawned by this batch or was despawned",
+                    index
+                )
+            }
+            ActionError::PermissionDenied(capability) => {
+                write!(
+                    f,
+                    "Permission denied, missing capability {}",
+                    capability.name()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
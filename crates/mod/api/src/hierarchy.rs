@@ -0,0 +1,37 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::ecs::Entity;
+
+/// Mod side representation of `bevy_hierarchy::Parent`.
+/// Host maps `Parent` to this type when sending components to mods.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy)]
+pub struct Parent(pub Entity);
+
+/// Mod side representation of `bevy_hierarchy::Children`.
+/// Host maps `Children` to this type when sending components to mods.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct Children(pub Vec<Entity>);
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    #[default]
+    Parent,
+    Children,
+    /// All parents up to the root, starting at the direct parent.
+    Ancestors,
+    /// All children recursively, in depth-first order.
+    Descendants,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy)]
+pub struct GetRelatives {
+    pub entity: Entity,
+    pub relation: Relation,
+}
+
+/// Sets the parent of the given entity. Setting it to [`None`] makes the entity a root entity.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy)]
+pub struct SetParent {
+    pub entity: Entity,
+    pub parent: Option<Entity>,
+}
@@ -0,0 +1,48 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::asset::AssetHandle;
+
+/// Pixel formats mods can use to create images. Mirrors a subset of `TextureFormat`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    R8Unorm,
+    Rg8Unorm,
+    Rgba8Unorm,
+    #[default]
+    Rgba8UnormSrgb,
+    R32Float,
+    Rgba32Float,
+}
+
+impl ImageFormat {
+    /// Size of a single pixel, in bytes.
+    pub fn pixel_size(self) -> usize {
+        match self {
+            ImageFormat::R8Unorm => 1,
+            ImageFormat::Rg8Unorm => 2,
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb | ImageFormat::R32Float => 4,
+            ImageFormat::Rgba32Float => 16,
+        }
+    }
+}
+
+/// Creates a new 2D `Image` asset. `data` holds `width * height` pixels, row by row, starting at
+/// the top left corner. Host responds with its [`AssetHandle`].
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct CreateImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageFormat,
+    pub data: Vec<u8>,
+}
+
+/// Overwrites a region of an existing image, using the format it was created with.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct UpdateImage {
+    pub handle: AssetHandle,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
@@ -0,0 +1,66 @@
+use bevy_math::Vec2;
+use bevy_reflect::{FromReflect, Reflect};
+
+/// Buttons which are pressed, or changed on current frame. Buttons are named after Bevy
+/// variants, like `Space` or `A` for `KeyCode`, and `Left` for `MouseButton`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct ButtonState {
+    pub pressed: Vec<String>,
+    pub just_pressed: Vec<String>,
+    pub just_released: Vec<String>,
+}
+
+impl ButtonState {
+    pub fn pressed(&self, button: &str) -> bool {
+        self.pressed.iter().any(|pressed| pressed == button)
+    }
+
+    pub fn just_pressed(&self, button: &str) -> bool {
+        self.just_pressed.iter().any(|pressed| pressed == button)
+    }
+
+    pub fn just_released(&self, button: &str) -> bool {
+        self.just_released.iter().any(|released| released == button)
+    }
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct AxisValue {
+    /// Named after `GamepadAxisType` variants, like `LeftStickX`.
+    pub axis: String,
+    pub value: f32,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct GamepadState {
+    pub id: usize,
+    /// Named after `GamepadButtonType` variants, like `South` or `LeftTrigger`.
+    pub buttons: ButtonState,
+    pub axes: Vec<AxisValue>,
+}
+
+impl GamepadState {
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axes
+            .iter()
+            .find(|value| value.axis == axis)
+            .map_or(0.0, |value| value.value)
+    }
+}
+
+/// Read-only snapshot of input on current frame. Inputs which aren't available on host, like
+/// when there is no window, are empty.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct InputState {
+    pub keys: ButtonState,
+    pub mouse_buttons: ButtonState,
+    /// Cursor position on primary window, if it's inside the window.
+    pub cursor_position: Option<Vec2>,
+    pub gamepads: Vec<GamepadState>,
+}
+
+impl InputState {
+    pub fn gamepad(&self, id: usize) -> Option<&GamepadState> {
+        self.gamepads.iter().find(|gamepad| gamepad.id == id)
+    }
+}
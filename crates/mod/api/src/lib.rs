@@ -1,7 +1,22 @@
+pub mod asset;
+pub mod call;
+pub mod capability;
+pub mod commands;
 pub mod ecs;
+pub mod error;
+pub mod hierarchy;
+pub mod image;
+pub mod input;
 pub mod log;
+pub mod manifest;
+pub mod material;
+pub mod mesh;
 pub mod query;
 pub mod registry;
+pub mod storage;
+pub mod system;
+pub mod time;
+pub mod value;
 
 pub(crate) mod reflect_proxy;
 
@@ -9,7 +24,28 @@ pub(crate) mod reflect_proxy;
 #[repr(u8)]
 pub enum Action {
     LOG,
+    PANIC,
     QUERY,
+    SPAWN,
+    GET_RELATIVES,
+    SET_PARENT,
+    REGISTER_SYSTEM,
+    CALL_MOD,
+    STORAGE_GET,
+    STORAGE_SET,
+    STORAGE_REMOVE,
+    INPUT,
+    LOAD_ASSET,
+    GET_LOAD_STATE,
+    RELEASE_ASSET,
+    CREATE_MESH,
+    UPDATE_MESH,
+    CREATE_MATERIAL,
+    PATCH_MATERIAL,
+    CREATE_IMAGE,
+    UPDATE_IMAGE,
+    RANDOM_SEED,
+    COMMANDS,
 
     TEST = 254,
     #[default]
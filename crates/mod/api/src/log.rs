@@ -1,8 +1,48 @@
 use bevy_reflect::{FromReflect, Reflect};
 
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
 #[derive(Reflect, FromReflect, Debug, Default)]
 pub struct LogMessage {
-    pub level: u8,
+    pub level: LogLevel,
+    pub message: String,
+    /// Target of the log record, usually the module path which logged it.
+    /// Empty when logged through SDK log functions.
+    pub target: String,
+    pub module_path: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// Sent by mod panic hook, right before the panic traps the module.
+#[derive(Reflect, FromReflect, Debug, Default, Clone)]
+pub struct PanicMessage {
     pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
 }
 
+impl std::fmt::Display for PanicMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        if let Some(file) = &self.file {
+            write!(f, " at {}", file)?;
+
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, ":{}:{}", line, column)?;
+            }
+        }
+
+        Ok(())
+    }
+}
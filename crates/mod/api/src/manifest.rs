@@ -0,0 +1,32 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+/// Version of the API between host and mods. Mods are built against the version of their SDK.
+pub const API_VERSION: &str = "0.1.0";
+
+/// Name of the wasm custom section which holds the serialized [`ModManifest`].
+pub const MANIFEST_SECTION: &str = "wabi_manifest";
+
+/// Another mod which must be loaded before this one.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct Dependency {
+    pub id: String,
+    /// Semver requirement, like `^0.2` or `>=1.0, <2.0`.
+    pub version_req: String,
+}
+
+/// Metadata embedded on mods by `wabi::manifest!()`, using `[package]` and
+/// `[package.metadata.wabi]` sections of mod's `Cargo.toml`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct ModManifest {
+    /// Unique identifier of the mod. Defaults to package name.
+    pub id: String,
+    /// Display name. Defaults to package name.
+    pub name: String,
+    pub version: String,
+    pub authors: Vec<String>,
+    /// [`API_VERSION`] of the SDK used to build the mod.
+    pub api_version: String,
+    pub dependencies: Vec<Dependency>,
+    /// Names of requested [`crate::capability::Capability`], like `query` or `storage`.
+    pub capabilities: Vec<String>,
+}
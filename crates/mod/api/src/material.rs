@@ -0,0 +1,36 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::{asset::AssetHandle, value::Value};
+
+/// Sets a `StandardMaterial` field, using a reflect path like `base_color` or `perceptual_roughness`.
+///
+/// Colors are given as `Vec4` (or `Vec3`) in sRGB and textures as [`AssetHandle`] of an `Image`,
+/// or `Option<AssetHandle>` to remove it.
+#[derive(Reflect, FromReflect, Debug, Clone)]
+pub struct MaterialField {
+    pub path: String,
+    pub value: Value,
+}
+
+impl MaterialField {
+    pub fn new<T: Reflect>(path: &str, value: T) -> Self {
+        Self {
+            path: path.to_string(),
+            value: Value::new(value),
+        }
+    }
+}
+
+/// Creates a `StandardMaterial` with default values and the given fields.
+/// Host responds with its [`AssetHandle`].
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct CreateMaterial {
+    pub fields: Vec<MaterialField>,
+}
+
+/// Changes only the given fields of an existing material. If any field is invalid, none are changed.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct PatchMaterial {
+    pub handle: AssetHandle,
+    pub fields: Vec<MaterialField>,
+}
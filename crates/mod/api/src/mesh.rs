@@ -0,0 +1,40 @@
+use bevy_math::{Vec2, Vec3};
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::asset::AssetHandle;
+
+/// Mirrors `PrimitiveTopology`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    #[default]
+    TriangleList,
+    TriangleStrip,
+}
+
+/// Vertex data of a mesh. `normals` and `uvs` are optional, but when given they must have the
+/// same length as `positions`. Indices, or positions when there are no indices, must make whole
+/// primitives of `topology`, like multiples of 3 on `TriangleList`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct MeshData {
+    pub topology: MeshTopology,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub indices: Option<Vec<u32>>,
+}
+
+/// Creates a new `Mesh` asset. Host responds with its [`AssetHandle`].
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct CreateMesh {
+    pub data: MeshData,
+}
+
+/// Replaces the data of an existing `Mesh` asset, so entities using it are updated.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct UpdateMesh {
+    pub handle: AssetHandle,
+    pub data: MeshData,
+}
@@ -1,6 +1,9 @@
 use bevy_reflect::{FromReflect, Reflect};
 
-use crate::ecs::{Component, Entity};
+use crate::{
+    ecs::{Component, Entity},
+    value::Value,
+};
 
 #[derive(Reflect, Default, Debug, FromReflect, Clone)]
 pub enum Filter {
@@ -8,11 +11,52 @@ pub enum Filter {
     None,
     With(String),
     Without(String),
+    /// Only matches entities where the value at the given field satisfies the predicate.
+    /// The component is implicitly required.
+    Field {
+        field: FieldPath,
+        predicate: Predicate,
+    },
+}
+
+/// A single field inside a component, using [`bevy_reflect::GetPath`] syntax.
+/// E.g.: `translation.x` on `Transform`.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct FieldPath {
+    pub component: String,
+    pub path: String,
+}
+
+/// Condition evaluated by host against a field value.
+///
+/// Numeric values are compared regardless of their primitive type, so an `i32` can be
+/// compared to a `f32` field. String predicates works on `String` and `Cow<str>` fields.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub enum Predicate {
+    #[default]
+    Any,
+    Eq(Value),
+    Ne(Value),
+    Lt(Value),
+    Le(Value),
+    Gt(Value),
+    Ge(Value),
+    /// Matches values in `min..max`, so `min` is inclusive and `max` is exclusive.
+    Range {
+        min: Value,
+        max: Value,
+    },
+    StartsWith(String),
+    EndsWith(String),
+    Contains(String),
 }
 
 #[derive(Reflect, FromReflect, Default, Debug)]
 pub struct Query {
     pub components: Vec<String>,
+    /// Components which are fetched when present, but doesn't restrict which entities matches.
+    pub optional: Vec<String>,
+    pub fields: Vec<FieldPath>,
     pub filters: Vec<Filter>,
 }
 
@@ -20,6 +64,10 @@ pub struct Query {
 pub struct QueryFetchItem {
     pub entity: Entity,
     pub components: Vec<Component>,
+    /// Values of [`Query::optional`], in the same order.
+    pub optional: Vec<Option<Component>>,
+    /// Values of [`Query::fields`], in the same order.
+    pub fields: Vec<Value>,
 }
 
 #[derive(Reflect, FromReflect, Default, Debug)]
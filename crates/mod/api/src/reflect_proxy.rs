@@ -53,6 +53,12 @@ macro_rules! impl_type {
             }
         }
 
+        impl Clone for $ty {
+            fn clone(&self) -> Self {
+                Self(self.0.clone_value())
+            }
+        }
+
         impl FromReflect for $ty {
             fn from_reflect(reflect: &dyn Reflect) -> Option<Self> {
                 Some(Self(reflect.clone_value()))
@@ -4,8 +4,23 @@ use bevy::utils::HashSet;
 use bevy_reflect::TypeRegistry;
 
 use crate::{
-    log::LogMessage,
-    query::{Query, QueryFetch, QueryFetchItem},
+    asset::{AssetHandle, AssetLoadState, GetLoadState, LoadAsset, ReleaseAsset},
+    call::CallMod,
+    capability::Capability,
+    commands::{Command, CommandBatch, CommandEntity},
+    ecs::{Component, Entity, EntityList, Spawn},
+    error::ActionError,
+    hierarchy::{Children, GetRelatives, Parent, Relation, SetParent},
+    image::{CreateImage, ImageFormat, UpdateImage},
+    input::{AxisValue, ButtonState, GamepadState, InputState},
+    log::{LogLevel, LogMessage, PanicMessage},
+    manifest::{Dependency, ModManifest},
+    material::{CreateMaterial, MaterialField, PatchMaterial},
+    mesh::{CreateMesh, MeshData, MeshTopology, UpdateMesh},
+    query::{FieldPath, Filter, Predicate, Query, QueryFetch, QueryFetchItem},
+    storage::{StorageGet, StorageRemove, StorageSet, StorageValue},
+    system::SystemInfo,
+    time::TimeInfo,
 };
 
 pub fn create_type_registry() -> TypeRegistry {
@@ -18,17 +33,81 @@ pub fn create_type_registry() -> TypeRegistry {
 }
 
 pub fn register_api_types(registry: &mut TypeRegistry) {
+    registry.register::<ActionError>();
+    registry.register::<Entity>();
+    registry.register::<Option<Entity>>();
+    registry.register::<Vec<Entity>>();
+    registry.register::<EntityList>();
+    registry.register::<Spawn>();
+    registry.register::<CommandEntity>();
+    registry.register::<Option<CommandEntity>>();
+    registry.register::<Command>();
+    registry.register::<Vec<Command>>();
+    registry.register::<CommandBatch>();
+    registry.register::<Parent>();
+    registry.register::<Children>();
+    registry.register::<Relation>();
+    registry.register::<GetRelatives>();
+    registry.register::<SetParent>();
+    registry.register::<LogLevel>();
+    registry.register::<Option<LogLevel>>();
     registry.register::<LogMessage>();
+    registry.register::<PanicMessage>();
     registry.register::<Query>();
+    registry.register::<FieldPath>();
+    registry.register::<Filter>();
+    registry.register::<Predicate>();
     registry.register::<QueryFetch>();
     registry.register::<QueryFetchItem>();
+    registry.register::<Option<Component>>();
+    registry.register::<SystemInfo>();
+    registry.register::<TimeInfo>();
+    registry.register::<ButtonState>();
+    registry.register::<AxisValue>();
+    registry.register::<Vec<AxisValue>>();
+    registry.register::<GamepadState>();
+    registry.register::<Vec<GamepadState>>();
+    registry.register::<InputState>();
+    registry.register::<AssetHandle>();
+    registry.register::<Option<AssetHandle>>();
+    registry.register::<LoadAsset>();
+    registry.register::<AssetLoadState>();
+    registry.register::<GetLoadState>();
+    registry.register::<ReleaseAsset>();
+    registry.register::<MeshTopology>();
+    registry.register::<MeshData>();
+    registry.register::<CreateMesh>();
+    registry.register::<UpdateMesh>();
+    registry.register::<MaterialField>();
+    registry.register::<Vec<MaterialField>>();
+    registry.register::<CreateMaterial>();
+    registry.register::<PatchMaterial>();
+    registry.register::<ImageFormat>();
+    registry.register::<CreateImage>();
+    registry.register::<UpdateImage>();
+    registry.register::<Dependency>();
+    registry.register::<Vec<Dependency>>();
+    registry.register::<ModManifest>();
+    registry.register::<Capability>();
+    registry.register::<CallMod>();
+    registry.register::<StorageGet>();
+    registry.register::<StorageValue>();
+    registry.register::<StorageSet>();
+    registry.register::<StorageRemove>();
 }
 
 pub fn register_bevy_types(registry: &mut TypeRegistry) {
+    registry.register::<()>();
     registry.register::<Range<f32>>();
     registry.register::<HashSet<String>>();
     registry.register::<String>();
+    registry.register::<Vec<String>>();
+    registry.register::<Vec<u8>>();
+    registry.register::<Option<Vec<u8>>>();
+    registry.register::<Vec<u32>>();
+    registry.register::<Option<Vec<u32>>>();
     registry.register::<Option<String>>();
+    registry.register::<Option<u32>>();
 
     registry.register::<bevy_math::IVec2>();
     registry.register::<bevy_math::IVec3>();
@@ -45,7 +124,10 @@ pub fn register_bevy_types(registry: &mut TypeRegistry) {
     registry.register::<bevy_math::BVec4>();
     registry.register::<bevy_math::BVec4A>();
     registry.register::<bevy_math::Vec2>();
+    registry.register::<Option<bevy_math::Vec2>>();
+    registry.register::<Vec<bevy_math::Vec2>>();
     registry.register::<bevy_math::Vec3>();
+    registry.register::<Vec<bevy_math::Vec3>>();
     registry.register::<bevy_math::Vec3A>();
     registry.register::<bevy_math::Vec4>();
     registry.register::<bevy_math::DAffine2>();
@@ -0,0 +1,24 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+/// Values are serialized by mods, so host stores them as is and doesn't need to know their types.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct StorageGet {
+    pub key: String,
+}
+
+/// Response of [`StorageGet`]. `value` is `None` when the key doesn't exist.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct StorageValue {
+    pub value: Option<Vec<u8>>,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct StorageSet {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct StorageRemove {
+    pub key: String,
+}
@@ -0,0 +1,9 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+/// Registered by mods on the first run of a system, so host knows which components it uses.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct SystemInfo {
+    pub name: String,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+}
@@ -0,0 +1,19 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::log::LogLevel;
+
+/// Sent by host along with each system invocation.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct TimeInfo {
+    /// Seconds since the previous run. On fixed timestep, it's always the timestep.
+    pub delta_seconds: f64,
+    /// Seconds since app startup. On fixed timestep, it's the sum of all steps.
+    pub elapsed_seconds: f64,
+    /// Frames run by host so far. Mods may run more than once per frame on fixed timestep.
+    pub frame_count: u64,
+    /// Whether mods are running on fixed timestep.
+    pub fixed_timestep: bool,
+    /// Most verbose level host logs, or `None` when host logging is off.
+    /// Mods skip records above it instead of sending them.
+    pub max_log_level: Option<LogLevel>,
+}
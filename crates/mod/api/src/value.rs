@@ -0,0 +1,11 @@
+use bevy_reflect::{FromReflect, Reflect};
+
+use crate::reflect_proxy;
+
+reflect_proxy::impl_type!(Value);
+
+impl Value {
+    pub fn new<T: Reflect>(value: T) -> Self {
+        Self(Box::new(value))
+    }
+}
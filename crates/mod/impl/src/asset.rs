@@ -0,0 +1,30 @@
+use wabi_mod_api::{
+    asset::{AssetHandle, AssetLoadState, GetLoadState, LoadAsset, ReleaseAsset},
+    error::ActionError,
+    Action,
+};
+
+use crate::io::{send_command, send_request};
+
+/// Loads an asset on a path relative to the mod file, like `textures/grass.png`.
+/// `asset_type` is the asset short type name, like `Mesh`, `Image` or `StandardMaterial`.
+///
+/// The returned handle can be added as a component when spawning entities.
+pub fn load(path: &str, asset_type: &str) -> Result<AssetHandle, ActionError> {
+    send_request(
+        &LoadAsset {
+            path: path.to_string(),
+            asset_type: asset_type.to_string(),
+        },
+        Action::LOAD_ASSET,
+    )
+}
+
+pub fn load_state(handle: AssetHandle) -> Result<AssetLoadState, ActionError> {
+    send_request(&GetLoadState { handle }, Action::GET_LOAD_STATE)
+}
+
+/// Releases the mod reference to the asset. Entities using it aren't affected.
+pub fn release(handle: AssetHandle) -> Result<(), ActionError> {
+    send_command(&ReleaseAsset { handle }, Action::RELEASE_ASSET)
+}
@@ -0,0 +1,91 @@
+use std::fmt::Display;
+
+use bevy_reflect::{FromReflect, GetTypeRegistration, Reflect, TypePath};
+use wabi_mod_api::{call::CallMod, error::ActionError, Action};
+
+use crate::{
+    commands,
+    io::{self, send_request},
+};
+
+/// Calls a function exported by another mod with `#[wabi::export]`.
+///
+/// Argument and return types must be known by both mods. Types which aren't on the SDK registry
+/// are registered on call, but their fields must be registered already, like primitives, strings
+/// and math types. See [`CallMod`] for re-entrancy rules.
+pub fn call<A, R>(module: &str, function: &str, args: A) -> Result<R, ActionError>
+where
+    A: Reflect + GetTypeRegistration,
+    R: FromReflect + TypePath + GetTypeRegistration,
+{
+    io::register_type::<A>();
+    io::register_type::<R>();
+
+    let call = CallMod {
+        module: module.to_string(),
+        function: function.to_string(),
+        args: io::serialize(&args),
+    };
+
+    send_request(&call, Action::CALL_MOD)
+}
+
+fn read_args<A>(function: &str, len: u32) -> Result<A, ActionError>
+where
+    A: FromReflect + TypePath + GetTypeRegistration,
+{
+    io::register_type::<A>();
+
+    io::deserialize(io::read_buffer(len))
+        .and_then(|args| A::from_reflect(&*args))
+        .ok_or_else(|| ActionError::ExportFailed {
+            function: function.to_string(),
+            reason: format!(
+                "Invalid arguments, expected {}",
+                <A as TypePath>::type_path()
+            ),
+        })
+}
+
+fn write_result(result: Result<Box<dyn Reflect>, ActionError>) -> u32 {
+    commands::flush_or_log();
+
+    let buffer = match result {
+        Ok(value) => io::serialize(&*value),
+        Err(err) => io::serialize(&err),
+    };
+
+    io::write_buffer(&buffer)
+}
+
+#[doc(hidden)]
+pub fn run_export<A, R>(function: &str, len: u32, export: impl Fn(A) -> R) -> u32
+where
+    A: FromReflect + TypePath + GetTypeRegistration,
+    R: Reflect,
+{
+    commands::clear();
+    write_result(read_args(function, len).map(|args| Box::new(export(args)) as Box<dyn Reflect>))
+}
+
+#[doc(hidden)]
+pub fn run_export_result<A, R, E>(
+    function: &str,
+    len: u32,
+    export: impl Fn(A) -> Result<R, E>,
+) -> u32
+where
+    A: FromReflect + TypePath + GetTypeRegistration,
+    R: Reflect,
+    E: Display,
+{
+    commands::clear();
+    write_result(read_args(function, len).and_then(|args| {
+        export(args)
+            .map(|value| Box::new(value) as Box<dyn Reflect>)
+            .map_err(|err| ActionError::ExportFailed {
+                function: function.to_string(),
+                reason: err.to_string(),
+            })
+    }))
+}
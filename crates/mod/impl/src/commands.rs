@@ -0,0 +1,147 @@
+use bevy_reflect::{Reflect, TypePath};
+use wabi_mod_api::{
+    commands::{Command, CommandBatch, CommandEntity},
+    ecs::Component,
+    error::ActionError,
+    query::FieldPath,
+    value::Value,
+    Action,
+};
+
+use crate::{io::send_command, wabi::error};
+
+static mut COMMANDS: Commands = Commands {
+    commands: Vec::new(),
+    spawned: 0,
+};
+
+/// Records changes to be applied by host in a single batch, when the system or export returns,
+/// instead of a round trip per change.
+///
+/// Entities spawned here don't exist until the batch is applied, so they are returned as a
+/// [`CommandEntity::Pending`], which can be used by other commands of the same batch only.
+#[derive(Debug, Default)]
+pub struct Commands {
+    commands: Vec<Command>,
+    spawned: u32,
+}
+
+impl Commands {
+    pub fn spawn(&mut self, components: Vec<Component>) -> CommandEntity {
+        self.push_spawn(components, None)
+    }
+
+    pub fn spawn_child(
+        &mut self,
+        parent: impl Into<CommandEntity>,
+        components: Vec<Component>,
+    ) -> CommandEntity {
+        self.push_spawn(components, Some(parent.into()))
+    }
+
+    fn push_spawn(
+        &mut self,
+        components: Vec<Component>,
+        parent: Option<CommandEntity>,
+    ) -> CommandEntity {
+        self.commands.push(Command::Spawn { components, parent });
+
+        let entity = CommandEntity::Pending(self.spawned);
+        self.spawned += 1;
+        entity
+    }
+
+    pub fn insert(
+        &mut self,
+        entity: impl Into<CommandEntity>,
+        components: Vec<Component>,
+    ) -> &mut Self {
+        self.commands.push(Command::Insert {
+            entity: entity.into(),
+            components,
+        });
+        self
+    }
+
+    pub fn remove<T: TypePath>(&mut self, entity: impl Into<CommandEntity>) -> &mut Self {
+        self.remove_by_name(entity, <T as TypePath>::type_path())
+    }
+
+    /// Removes a component by its name, which can be an alias or a short name, like on queries.
+    pub fn remove_by_name(&mut self, entity: impl Into<CommandEntity>, name: &str) -> &mut Self {
+        self.commands.push(Command::Remove {
+            entity: entity.into(),
+            components: vec![name.to_string()],
+        });
+        self
+    }
+
+    /// Despawns the entity along with all its descendants.
+    pub fn despawn(&mut self, entity: impl Into<CommandEntity>) -> &mut Self {
+        self.commands.push(Command::Despawn {
+            entity: entity.into(),
+        });
+        self
+    }
+
+    /// Sets a single field of a component, like `translation.x` on `Transform`.
+    pub fn set<T: Reflect>(
+        &mut self,
+        entity: impl Into<CommandEntity>,
+        component: &str,
+        path: &str,
+        value: T,
+    ) -> &mut Self {
+        self.commands.push(Command::Set {
+            entity: entity.into(),
+            field: FieldPath {
+                component: component.to_string(),
+                path: path.to_string(),
+            },
+            value: Value::new(value),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Command buffer of this mod.
+pub fn commands() -> &'static mut Commands {
+    // SAFETY: Wasm modules are single threaded
+    unsafe { &mut COMMANDS }
+}
+
+/// Sends recorded commands to host right away. This is done automatically when a system or export
+/// returns. Pending entities of previous batches can't be used after flushing.
+pub fn flush() -> Result<(), ActionError> {
+    let commands = std::mem::take(commands());
+
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    send_command(
+        &CommandBatch {
+            commands: commands.commands,
+        },
+        Action::COMMANDS,
+    )
+}
+
+/// Discards commands left behind by a previous run which trapped before flushing them.
+pub(crate) fn clear() {
+    *commands() = Commands::default();
+}
+
+pub(crate) fn flush_or_log() {
+    if let Err(err) = flush() {
+        error(format!("Failed to apply commands: {}", err));
+    }
+}
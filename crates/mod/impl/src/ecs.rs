@@ -0,0 +1,27 @@
+use wabi_mod_api::{
+    ecs::{Component, Entity, Spawn},
+    error::ActionError,
+    Action,
+};
+
+use crate::io::send_request;
+
+pub fn spawn(components: Vec<Component>) -> Result<Entity, ActionError> {
+    send_request(
+        &Spawn {
+            components,
+            parent: None,
+        },
+        Action::SPAWN,
+    )
+}
+
+pub fn spawn_child(parent: Entity, components: Vec<Component>) -> Result<Entity, ActionError> {
+    send_request(
+        &Spawn {
+            components,
+            parent: Some(parent),
+        },
+        Action::SPAWN,
+    )
+}
@@ -0,0 +1,49 @@
+use wabi_mod_api::{
+    ecs::{Entity, EntityList},
+    error::ActionError,
+    hierarchy::{GetRelatives, Relation, SetParent},
+    Action,
+};
+
+use crate::io::{send_command, send_request};
+
+fn get_relatives(entity: Entity, relation: Relation) -> Result<Vec<Entity>, ActionError> {
+    let list: EntityList = send_request(&GetRelatives { entity, relation }, Action::GET_RELATIVES)?;
+    Ok(list.entities)
+}
+
+pub fn parent(entity: Entity) -> Result<Option<Entity>, ActionError> {
+    Ok(get_relatives(entity, Relation::Parent)?.first().copied())
+}
+
+pub fn children(entity: Entity) -> Result<Vec<Entity>, ActionError> {
+    get_relatives(entity, Relation::Children)
+}
+
+pub fn ancestors(entity: Entity) -> Result<Vec<Entity>, ActionError> {
+    get_relatives(entity, Relation::Ancestors)
+}
+
+pub fn descendants(entity: Entity) -> Result<Vec<Entity>, ActionError> {
+    get_relatives(entity, Relation::Descendants)
+}
+
+pub fn set_parent(entity: Entity, parent: Entity) -> Result<(), ActionError> {
+    send_command(
+        &SetParent {
+            entity,
+            parent: Some(parent),
+        },
+        Action::SET_PARENT,
+    )
+}
+
+pub fn remove_parent(entity: Entity) -> Result<(), ActionError> {
+    send_command(
+        &SetParent {
+            entity,
+            parent: None,
+        },
+        Action::SET_PARENT,
+    )
+}
@@ -0,0 +1,49 @@
+use wabi_mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    image::{CreateImage, ImageFormat, UpdateImage},
+    Action,
+};
+
+use crate::io::{send_command, send_request};
+
+/// Creates a 2D `Image` asset on host from raw pixels, which must have `width * height` pixels
+/// of the given format. The returned handle can be used as a texture in materials.
+pub fn create(
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    data: Vec<u8>,
+) -> Result<AssetHandle, ActionError> {
+    send_request(
+        &CreateImage {
+            width,
+            height,
+            format,
+            data,
+        },
+        Action::CREATE_IMAGE,
+    )
+}
+
+/// Overwrites the region starting at `(x, y)` with `width * height` pixels, in the image format.
+pub fn update(
+    handle: AssetHandle,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) -> Result<(), ActionError> {
+    send_command(
+        &UpdateImage {
+            handle,
+            x,
+            y,
+            width,
+            height,
+            data,
+        },
+        Action::UPDATE_IMAGE,
+    )
+}
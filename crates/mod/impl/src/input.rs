@@ -0,0 +1,82 @@
+use bevy_math::Vec2;
+use wabi_mod_api::{error::ActionError, input::InputState, Action};
+
+use crate::{io::send_request, time, wabi::error};
+
+static mut SNAPSHOT: Option<(u64, InputState)> = None;
+
+/// Requests the input snapshot of current frame from host.
+pub fn fetch() -> Result<InputState, ActionError> {
+    send_request(&(), Action::INPUT)
+}
+
+/// Input snapshot of current frame. It's requested only once per frame and reused by helpers
+/// below. When it can't be requested, nothing is pressed.
+pub fn input() -> Option<&'static InputState> {
+    let frame = time::frame_count();
+
+    // SAFETY: Wasm modules are single threaded
+    unsafe {
+        if !matches!(&SNAPSHOT, Some((snapshot_frame, _)) if *snapshot_frame == frame) {
+            SNAPSHOT = match fetch() {
+                Ok(state) => Some((frame, state)),
+                Err(err) => {
+                    error(format!("Failed to fetch input: {}", err));
+                    None
+                }
+            };
+        }
+
+        SNAPSHOT.as_ref().map(|(_, state)| state)
+    }
+}
+
+/// Whether the key is pressed. Keys are named after `KeyCode` variants, like `Space` or `A`.
+pub fn pressed(key: &str) -> bool {
+    input().map_or(false, |input| input.keys.pressed(key))
+}
+
+pub fn just_pressed(key: &str) -> bool {
+    input().map_or(false, |input| input.keys.just_pressed(key))
+}
+
+pub fn just_released(key: &str) -> bool {
+    input().map_or(false, |input| input.keys.just_released(key))
+}
+
+/// Whether the mouse button is pressed. Buttons are named after `MouseButton` variants, like `Left`.
+pub fn mouse_pressed(button: &str) -> bool {
+    input().map_or(false, |input| input.mouse_buttons.pressed(button))
+}
+
+pub fn mouse_just_pressed(button: &str) -> bool {
+    input().map_or(false, |input| input.mouse_buttons.just_pressed(button))
+}
+
+pub fn mouse_just_released(button: &str) -> bool {
+    input().map_or(false, |input| input.mouse_buttons.just_released(button))
+}
+
+pub fn cursor_position() -> Option<Vec2> {
+    input().and_then(|input| input.cursor_position)
+}
+
+/// Buttons are named after `GamepadButtonType` variants, like `South`.
+pub fn gamepad_pressed(gamepad: usize, button: &str) -> bool {
+    input()
+        .and_then(|input| input.gamepad(gamepad))
+        .map_or(false, |gamepad| gamepad.buttons.pressed(button))
+}
+
+pub fn gamepad_just_pressed(gamepad: usize, button: &str) -> bool {
+    input()
+        .and_then(|input| input.gamepad(gamepad))
+        .map_or(false, |gamepad| gamepad.buttons.just_pressed(button))
+}
+
+/// Axes are named after `GamepadAxisType` variants, like `LeftStickX`.
+pub fn gamepad_axis(gamepad: usize, axis: &str) -> f32 {
+    input()
+        .and_then(|input| input.gamepad(gamepad))
+        .map_or(0.0, |gamepad| gamepad.axis(axis))
+}
@@ -3,11 +3,11 @@ use std::io::Write;
 use bevy_reflect::{
     erased_serde::__private::serde::de::DeserializeSeed,
     serde::{ReflectSerializer, UntypedReflectDeserializer},
-    Reflect, TypeRegistry,
+    FromReflect, GetTypeRegistration, Reflect, TypePath, TypeRegistry,
 };
-use wabi_mod_api::{registry::create_type_registry, Action};
+use wabi_mod_api::{error::ActionError, registry::create_type_registry, Action};
 
-use crate::wabi::error;
+use crate::{logger, panic, wabi::error};
 
 const PAGE_SIZE: usize = 65536;
 
@@ -35,6 +35,69 @@ impl InstanceData {
     }
 }
 
+/// Registers a type which isn't known by the SDK, like types defined by mods.
+pub(crate) fn register_type<T: GetTypeRegistration>() {
+    get_instance_data()
+        .registry
+        .as_mut()
+        .expect("Registry should be created on alloc")
+        .register::<T>();
+}
+
+/// Serializes data using the same format used to talk with host.
+pub(crate) fn serialize(data: &dyn Reflect) -> Vec<u8> {
+    let reflect_serializer = ReflectSerializer::new(data, get_instance_data().get_registry());
+
+    let result = {
+        #[cfg(not(feature = "json"))]
+        {
+            rmp_serde::encode::to_vec(&reflect_serializer).map_err(|err| err.to_string())
+        }
+        #[cfg(feature = "json")]
+        {
+            serde_json::to_vec(&reflect_serializer).map_err(|err| err.to_string())
+        }
+    };
+
+    result.unwrap_or_else(|err| {
+        error(format!("Failed to serialize {}: {}", data.type_path(), err));
+        vec![]
+    })
+}
+
+pub(crate) fn deserialize(buffer: &[u8]) -> Option<Box<dyn Reflect>> {
+    let reflect_deserializer = UntypedReflectDeserializer::new(get_instance_data().get_registry());
+    let mut deserializer = {
+        #[cfg(not(feature = "json"))]
+        {
+            rmp_serde::Deserializer::from_read_ref(buffer)
+        }
+        #[cfg(feature = "json")]
+        {
+            serde_json::Deserializer::from_slice(buffer)
+        }
+    };
+
+    match reflect_deserializer.deserialize(&mut deserializer) {
+        Ok(data) => Some(data),
+        Err(err) => {
+            error(format!("Failed to deserialize: {:?}", err));
+            None
+        }
+    }
+}
+
+/// Reads data written by host on the buffer, like arguments of exported functions.
+pub(crate) fn read_buffer(len: u32) -> &'static [u8] {
+    &get_instance_data().buffer[..len as usize]
+}
+
+/// Writes data on the buffer, so host can read it. Returns the written length.
+pub(crate) fn write_buffer(data: &[u8]) -> u32 {
+    get_instance_data().buffer[..data.len()].copy_from_slice(data);
+    data.len() as u32
+}
+
 #[no_mangle]
 pub extern "C" fn __wabi_alloc(id: u32) -> i32 {
     // SAFETY: this function will be called only by host, so only one mutable access at any given time.
@@ -45,6 +108,9 @@ pub extern "C" fn __wabi_alloc(id: u32) -> i32 {
             registry: Some(create_type_registry()),
         };
 
+        logger::init();
+        panic::init();
+
         INSTANCE_DATA.buffer.as_ptr() as i32
     }
 }
@@ -111,6 +177,35 @@ pub fn send_action(data: &dyn Reflect, action: Action) -> Option<Box<dyn Reflect
     ActionWriter::new(action).send(data)
 }
 
+/// Sends an action which expects a response of type `T` or an [`ActionError`].
+pub fn send_request<T: FromReflect + TypePath>(
+    data: &dyn Reflect,
+    action: Action,
+) -> Result<T, ActionError> {
+    let invalid_response =
+        || ActionError::InvalidResponse(<T as TypePath>::type_path().to_string());
+
+    let response = send_action(data, action).ok_or_else(invalid_response)?;
+
+    if response.type_path() == <ActionError as TypePath>::type_path() {
+        Err(ActionError::from_reflect(response.as_ref()).unwrap_or_default())
+    } else {
+        T::from_reflect(response.as_ref()).ok_or_else(invalid_response)
+    }
+}
+
+/// Sends an action which only receives a response when it fails.
+pub fn send_command(data: &dyn Reflect, action: Action) -> Result<(), ActionError> {
+    match send_action(data, action) {
+        None => Ok(()),
+        Some(response) => Err(
+            ActionError::from_reflect(response.as_ref()).unwrap_or_else(|| {
+                ActionError::InvalidResponse(<ActionError as TypePath>::type_path().to_string())
+            }),
+        ),
+    }
+}
+
 #[derive(Default)]
 struct ActionWriter {
     len: usize,
@@ -152,7 +247,14 @@ impl std::io::Write for ActionWriter {
     }
 }
 
+#[cfg(not(test))]
 #[link(wasm_import_module = "wabi")]
 extern "C" {
     fn __wabi_process_action(id: u32, len: usize, action: u8) -> u32;
 }
+
+/// Stands for a host which never responds, so the SDK can be unit tested natively.
+#[cfg(test)]
+unsafe fn __wabi_process_action(_id: u32, _len: usize, _action: u8) -> u32 {
+    0
+}
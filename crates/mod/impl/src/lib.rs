@@ -17,7 +17,25 @@
 //     }
 // }
 
+// Allows macros to refer to this crate by name, even when used inside this crate.
+extern crate self as wabi_mod_impl;
+
+pub mod asset;
+pub mod call;
+pub mod commands;
+pub mod ecs;
+pub mod hierarchy;
+pub mod image;
+pub mod input;
 pub mod io;
+pub mod material;
+pub mod mesh;
+mod logger;
+mod panic;
 pub mod query;
+pub mod random;
+pub mod storage;
+pub mod system;
 pub mod test;
+pub mod time;
 pub mod wabi;
\ No newline at end of file
@@ -0,0 +1,80 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use wabi_mod_api::{
+    log::{LogLevel, LogMessage},
+    Action,
+};
+
+use crate::io::send_action;
+
+/// Forwards records of `log` crate to host. Crates using `tracing` are also forwarded when
+/// `tracing` feature is enabled, since `tracing` emits `log` records when there is no subscriber.
+struct HostLogger;
+
+impl Log for HostLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let level = match record.level() {
+            Level::Error => LogLevel::Error,
+            Level::Warn => LogLevel::Warn,
+            Level::Info => LogLevel::Info,
+            Level::Debug => LogLevel::Debug,
+            Level::Trace => LogLevel::Trace,
+        };
+
+        send_action(
+            &LogMessage {
+                level,
+                message: record.args().to_string(),
+                target: record.target().to_string(),
+                module_path: record.module_path().map(ToString::to_string),
+                file: record.file().map(ToString::to_string),
+                line: record.line(),
+            },
+            Action::LOG,
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: HostLogger = HostLogger;
+
+/// Installs the host logger. Does nothing if a logger was already installed.
+/// Everything is enabled until host sends its max level with the first time info.
+pub(crate) fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+/// Applies the max level sent by host, so filtered records aren't sent at all.
+pub(crate) fn set_max_level(level: Option<LogLevel>) {
+    log::set_max_level(match level {
+        None => LevelFilter::Off,
+        Some(LogLevel::Error) => LevelFilter::Error,
+        Some(LogLevel::Warn) => LevelFilter::Warn,
+        Some(LogLevel::Info) => LevelFilter::Info,
+        Some(LogLevel::Debug) => LevelFilter::Debug,
+        Some(LogLevel::Trace) => LevelFilter::Trace,
+    });
+}
+
+/// Whether host logs messages of given level.
+pub(crate) fn is_enabled(level: LogLevel) -> bool {
+    let level = match level {
+        LogLevel::Error => Level::Error,
+        LogLevel::Warn => Level::Warn,
+        LogLevel::Info => Level::Info,
+        LogLevel::Debug => Level::Debug,
+        LogLevel::Trace => Level::Trace,
+    };
+
+    level <= log::max_level()
+}
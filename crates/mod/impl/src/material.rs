@@ -0,0 +1,25 @@
+use wabi_mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    material::{CreateMaterial, MaterialField, PatchMaterial},
+    Action,
+};
+
+use crate::io::{send_command, send_request};
+
+/// Creates a `StandardMaterial` on host, with default values for fields which aren't given.
+///
+/// ```ignore
+/// let material = material::create(vec![
+///     MaterialField::new("base_color", Vec4::new(1.0, 0.0, 0.0, 1.0)),
+///     MaterialField::new("base_color_texture", texture),
+/// ])?;
+/// ```
+pub fn create(fields: Vec<MaterialField>) -> Result<AssetHandle, ActionError> {
+    send_request(&CreateMaterial { fields }, Action::CREATE_MATERIAL)
+}
+
+/// Changes the given fields of a material, like recoloring it.
+pub fn patch(handle: AssetHandle, fields: Vec<MaterialField>) -> Result<(), ActionError> {
+    send_command(&PatchMaterial { handle, fields }, Action::PATCH_MATERIAL)
+}
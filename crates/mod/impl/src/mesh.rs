@@ -0,0 +1,19 @@
+use wabi_mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    mesh::{CreateMesh, MeshData, UpdateMesh},
+    Action,
+};
+
+use crate::io::{send_command, send_request};
+
+/// Creates a `Mesh` asset on host. The returned handle can be added as a component when spawning
+/// entities, which then use this mesh.
+pub fn create(data: MeshData) -> Result<AssetHandle, ActionError> {
+    send_request(&CreateMesh { data }, Action::CREATE_MESH)
+}
+
+/// Replaces the data of a mesh, which updates all entities using it.
+pub fn update(handle: AssetHandle, data: MeshData) -> Result<(), ActionError> {
+    send_command(&UpdateMesh { handle, data }, Action::UPDATE_MESH)
+}
@@ -0,0 +1,36 @@
+use std::{panic::PanicInfo, sync::Once};
+
+use wabi_mod_api::{log::PanicMessage, Action};
+
+use crate::io::send_action;
+
+static PANIC_HOOK: Once = Once::new();
+
+fn panic_hook(info: &PanicInfo) {
+    let payload = info.payload();
+
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    };
+
+    let location = info.location();
+
+    send_action(
+        &PanicMessage {
+            message,
+            file: location.map(|l| l.file().to_string()),
+            line: location.map(|l| l.line()),
+            column: location.map(|l| l.column()),
+        },
+        Action::PANIC,
+    );
+}
+
+/// Installs a panic hook which sends panic message to host, before the module traps.
+pub(crate) fn init() {
+    PANIC_HOOK.call_once(|| std::panic::set_hook(Box::new(panic_hook)));
+}
@@ -1,17 +1,202 @@
-use bevy_reflect::FromReflect;
+use std::{fmt::Display, marker::PhantomData};
+
+use bevy_reflect::{FromReflect, Reflect, TypePath};
 use wabi_mod_api::{
-    query::{Query, QueryFetch, Filter},
+    ecs::{Component, Entity},
+    error::ActionError,
+    query::{Filter, Query, QueryFetch, QueryFetchItem},
     Action,
 };
 
-use crate::{io::send_action, wabi::unwrap};
+use crate::io::send_request;
+
+#[derive(Debug)]
+pub enum QueryError {
+    Action(ActionError),
+    /// A component received from host couldn't be converted to the requested type.
+    Decode(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::Action(err) => write!(f, "{}", err),
+            QueryError::Decode(type_path) => write!(f, "Failed to decode component {}", type_path),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<ActionError> for QueryError {
+    fn from(err: ActionError) -> Self {
+        QueryError::Action(err)
+    }
+}
+
+/// Components of a single [`QueryFetchItem`], which are consumed in the same order they were
+/// added on [`QueryData::add_to_query`].
+pub struct FetchState {
+    entity: Entity,
+    components: std::vec::IntoIter<Component>,
+    optional: std::vec::IntoIter<Option<Component>>,
+}
+
+impl From<QueryFetchItem> for FetchState {
+    fn from(item: QueryFetchItem) -> Self {
+        Self {
+            entity: item.entity,
+            components: item.components.into_iter(),
+            optional: item.optional.into_iter(),
+        }
+    }
+}
+
+fn decode<T: FromReflect + TypePath>(component: Option<Component>) -> Result<T, QueryError> {
+    component
+        .and_then(|component| T::from_reflect(component.as_reflect()))
+        .ok_or_else(|| QueryError::Decode(<T as TypePath>::type_path().to_string()))
+}
+
+/// Types which can be fetched by [`query`], like `&T`, `Option<&T>`, [`Entity`] or tuples
+/// of those.
+pub trait QueryData {
+    type Item;
+
+    fn add_to_query(query: &mut Query);
+    fn fetch(state: &mut FetchState) -> Result<Self::Item, QueryError>;
+}
+
+impl QueryData for Entity {
+    type Item = Entity;
+
+    fn add_to_query(_query: &mut Query) {}
+
+    fn fetch(state: &mut FetchState) -> Result<Self::Item, QueryError> {
+        Ok(state.entity)
+    }
+}
+
+impl<'a, T: FromReflect + TypePath> QueryData for &'a T {
+    type Item = T;
+
+    fn add_to_query(query: &mut Query) {
+        query
+            .components
+            .push(<T as TypePath>::type_path().to_string());
+    }
+
+    fn fetch(state: &mut FetchState) -> Result<Self::Item, QueryError> {
+        decode(state.components.next())
+    }
+}
+
+impl<'a, T: FromReflect + TypePath> QueryData for Option<&'a T> {
+    type Item = Option<T>;
 
-pub fn query(components: &[&'static str], filters: &[Filter]) -> QueryFetch {
-    let query = Query {
+    fn add_to_query(query: &mut Query) {
+        query
+            .optional
+            .push(<T as TypePath>::type_path().to_string());
+    }
+
+    fn fetch(state: &mut FetchState) -> Result<Self::Item, QueryError> {
+        match state.optional.next() {
+            Some(Some(component)) => decode(Some(component)).map(Some),
+            Some(None) => Ok(None),
+            None => Err(QueryError::Decode(<T as TypePath>::type_path().to_string())),
+        }
+    }
+}
+
+/// Filters which can be used by [`query`], like [`With`], [`Without`] or tuples of those.
+pub trait QueryFilter {
+    fn add_to_query(query: &mut Query);
+}
+
+impl QueryFilter for () {
+    fn add_to_query(_query: &mut Query) {}
+}
+
+pub struct With<T>(PhantomData<T>);
+
+impl<T: TypePath> QueryFilter for With<T> {
+    fn add_to_query(query: &mut Query) {
+        query
+            .filters
+            .push(Filter::With(<T as TypePath>::type_path().to_string()));
+    }
+}
+
+pub struct Without<T>(PhantomData<T>);
+
+impl<T: TypePath> QueryFilter for Without<T> {
+    fn add_to_query(query: &mut Query) {
+        query
+            .filters
+            .push(Filter::Without(<T as TypePath>::type_path().to_string()));
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type Item = ($($name::Item,)*);
+
+            fn add_to_query(query: &mut Query) {
+                $($name::add_to_query(query);)*
+            }
+
+            fn fetch(state: &mut FetchState) -> Result<Self::Item, QueryError> {
+                Ok(($($name::fetch(state)?,)*))
+            }
+        }
+
+        impl<$($name: QueryFilter),*> QueryFilter for ($($name,)*) {
+            fn add_to_query(query: &mut Query) {
+                $($name::add_to_query(query);)*
+            }
+        }
+    };
+}
+
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+impl_query_tuple!(A, B, C, D, E, F, G);
+impl_query_tuple!(A, B, C, D, E, F, G, H);
+
+/// Queries host using types instead of component names.
+///
+/// ```ignore
+/// let items = query::<(Entity, &Transform, Option<&Velocity>), With<Name>>()?;
+/// ```
+pub fn query<Q: QueryData, F: QueryFilter>() -> Result<Vec<Q::Item>, QueryError> {
+    let mut query = Query::default();
+    Q::add_to_query(&mut query);
+    F::add_to_query(&mut query);
+
+    fetch(query)?
+        .items
+        .into_iter()
+        .map(|item| Q::fetch(&mut FetchState::from(item)))
+        .collect()
+}
+
+pub fn query_dynamic(
+    components: &[&'static str],
+    filters: &[Filter],
+) -> Result<QueryFetch, ActionError> {
+    fetch(Query {
         components: components.iter().map(ToString::to_string).collect(),
         filters: filters.into(),
-    };
+        ..Default::default()
+    })
+}
 
-    let result = unwrap!(send_action(&query, Action::QUERY));
-    unwrap!(QueryFetch::from_reflect(result.as_ref()))
+pub fn fetch(query: Query) -> Result<QueryFetch, ActionError> {
+    send_request(&query, Action::QUERY)
 }
@@ -0,0 +1,140 @@
+use std::ops::Range;
+
+use wabi_mod_api::{error::ActionError, Action};
+
+use crate::{io::send_request, wabi::error};
+
+static mut RNG: Option<Rng> = None;
+
+/// Requests the seed of this mod from host.
+pub fn fetch_seed() -> Result<u64, ActionError> {
+    send_request(&(), Action::RANDOM_SEED)
+}
+
+/// A small and fast xoshiro256** generator. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Expands the seed with splitmix64, as recommended by xoshiro authors.
+        let mut seed = seed;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+
+        Self {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+
+    /// Uniform value in `range`. Panics if it's empty.
+    pub fn range_u32(&mut self, range: Range<u32>) -> u32 {
+        assert!(range.start < range.end, "Empty range");
+        let span = (range.end - range.start) as u64;
+        range.start + ((self.next_u32() as u64 * span) >> 32) as u32
+    }
+
+    /// Uniform value in `range`. Panics if it's empty.
+    pub fn range_i32(&mut self, range: Range<i32>) -> i32 {
+        assert!(range.start < range.end, "Empty range");
+        let span = (range.end as i64 - range.start as i64) as u64;
+        (range.start as i64 + ((self.next_u32() as u64 * span) >> 32) as i64) as i32
+    }
+
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+
+    /// Creates a new generator seeded by this one, so a feature can have its own sequence of
+    /// numbers without changing the sequence of others.
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
+
+/// Mod generator, seeded by host on first use. Host seeds each mod per session, so a session can
+/// be replayed by using the same host seed. When the seed can't be requested, zero is used.
+pub fn rng() -> &'static mut Rng {
+    // SAFETY: Wasm modules are single threaded
+    unsafe {
+        RNG.get_or_insert_with(|| {
+            let seed = fetch_seed().unwrap_or_else(|err| {
+                error(format!("Failed to fetch random seed: {}", err));
+                0
+            });
+            Rng::new(seed)
+        })
+    }
+}
+
+pub fn u32() -> u32 {
+    rng().next_u32()
+}
+
+pub fn u64() -> u64 {
+    rng().next_u64()
+}
+
+pub fn f32() -> f32 {
+    rng().next_f32()
+}
+
+pub fn f64() -> f64 {
+    rng().next_f64()
+}
+
+pub fn bool() -> bool {
+    rng().next_bool()
+}
+
+pub fn range_u32(range: Range<u32>) -> u32 {
+    rng().range_u32(range)
+}
+
+pub fn range_i32(range: Range<i32>) -> i32 {
+    rng().range_i32(range)
+}
+
+pub fn range_f32(range: Range<f32>) -> f32 {
+    rng().range_f32(range)
+}
@@ -0,0 +1,55 @@
+use bevy_reflect::{FromReflect, GetTypeRegistration, Reflect, TypePath};
+use wabi_mod_api::{
+    error::ActionError,
+    storage::{StorageGet, StorageRemove, StorageSet, StorageValue},
+    Action,
+};
+
+use crate::io::{self, send_command, send_request};
+
+/// Reads a value stored by this mod on a previous [`set`], even on an earlier session.
+pub fn get<T>(key: &str) -> Result<Option<T>, ActionError>
+where
+    T: FromReflect + TypePath + GetTypeRegistration,
+{
+    let response: StorageValue = send_request(
+        &StorageGet {
+            key: key.to_string(),
+        },
+        Action::STORAGE_GET,
+    )?;
+
+    let value = match response.value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    io::register_type::<T>();
+
+    io::deserialize(&value)
+        .and_then(|value| T::from_reflect(&*value))
+        .map(Some)
+        .ok_or_else(|| ActionError::InvalidResponse(<T as TypePath>::type_path().to_string()))
+}
+
+/// Stores a value, replacing the previous one. Fails if the mod storage quota is exceeded.
+pub fn set<T: Reflect + GetTypeRegistration>(key: &str, value: &T) -> Result<(), ActionError> {
+    io::register_type::<T>();
+
+    send_command(
+        &StorageSet {
+            key: key.to_string(),
+            value: io::serialize(value),
+        },
+        Action::STORAGE_SET,
+    )
+}
+
+pub fn remove(key: &str) -> Result<(), ActionError> {
+    send_command(
+        &StorageRemove {
+            key: key.to_string(),
+        },
+        Action::STORAGE_REMOVE,
+    )
+}
@@ -0,0 +1,110 @@
+use std::{fmt::Display, sync::Once};
+
+use wabi_mod_api::{system::SystemInfo, Action};
+
+use crate::{commands, io::send_command, time, wabi::error};
+
+/// Generated by `#[wabi::system]` macro.
+pub struct SystemDescriptor {
+    pub name: &'static str,
+    pub reads: &'static [&'static str],
+    pub writes: &'static [&'static str],
+}
+
+/// Return types allowed on systems.
+pub trait IntoSystemResult {
+    fn into_system_result(self) -> Result<(), String>;
+}
+
+impl IntoSystemResult for () {
+    fn into_system_result(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<E: Display> IntoSystemResult for Result<(), E> {
+    fn into_system_result(self) -> Result<(), String> {
+        self.map_err(|err| err.to_string())
+    }
+}
+
+fn register(descriptor: &SystemDescriptor) {
+    let info = SystemInfo {
+        name: descriptor.name.to_string(),
+        reads: descriptor.reads.iter().map(ToString::to_string).collect(),
+        writes: descriptor.writes.iter().map(ToString::to_string).collect(),
+    };
+
+    if let Err(err) = send_command(&info, Action::REGISTER_SYSTEM) {
+        error(format!(
+            "Failed to register system {}: {}",
+            descriptor.name, err
+        ));
+    }
+}
+
+/// `setup` is unique per system, so each system is registered on its first run.
+#[doc(hidden)]
+pub fn run_system<R: IntoSystemResult>(
+    descriptor: SystemDescriptor,
+    setup: &'static Once,
+    len: u32,
+    system: fn() -> R,
+) {
+    // Read before sending any action, since actions overwrite the buffer.
+    time::update(len);
+    commands::clear();
+    setup.call_once(|| register(&descriptor));
+
+    if let Err(err) = system().into_system_result() {
+        error(format!("System {} failed: {}", descriptor.name, err));
+    }
+
+    commands::flush_or_log();
+}
+
+#[cfg(test)]
+mod tests {
+    use wabi_mod_api::{log::LogLevel, time::TimeInfo};
+
+    use super::*;
+    use crate::io;
+
+    static SETUP: Once = Once::new();
+    static mut SEEN: Option<TimeInfo> = None;
+
+    fn system() {
+        // SAFETY: Only this test runs the system
+        unsafe { SEEN = Some(time::time().clone()) };
+    }
+
+    #[test]
+    fn first_run_sees_host_time() {
+        io::__wabi_alloc(1);
+
+        let time = TimeInfo {
+            delta_seconds: 0.5,
+            elapsed_seconds: 10.0,
+            frame_count: 42,
+            fixed_timestep: true,
+            max_log_level: Some(LogLevel::Warn),
+        };
+        let len = io::write_buffer(&io::serialize(&time));
+
+        let descriptor = SystemDescriptor {
+            name: "system",
+            reads: &[],
+            writes: &[],
+        };
+        run_system(descriptor, &SETUP, len, system);
+
+        // SAFETY: System has already run
+        let seen = unsafe { SEEN.clone() }.expect("System should run");
+        assert_eq!(seen.frame_count, 42);
+        assert_eq!(seen.delta_seconds, 0.5);
+        assert_eq!(seen.elapsed_seconds, 10.0);
+        assert!(seen.fixed_timestep);
+        assert!(!crate::logger::is_enabled(LogLevel::Info));
+        assert!(crate::logger::is_enabled(LogLevel::Warn));
+    }
+}
@@ -0,0 +1,51 @@
+use bevy_reflect::FromReflect;
+use wabi_mod_api::{log::LogLevel, time::TimeInfo};
+
+use crate::{io, logger, wabi::error};
+
+static mut TIME: TimeInfo = TimeInfo {
+    delta_seconds: 0.0,
+    elapsed_seconds: 0.0,
+    frame_count: 0,
+    fixed_timestep: false,
+    max_log_level: Some(LogLevel::Trace),
+};
+
+/// Reads the time info sent by host along with the system invocation.
+pub(crate) fn update(len: u32) {
+    let time =
+        io::deserialize(io::read_buffer(len)).and_then(|time| TimeInfo::from_reflect(&*time));
+
+    match time {
+        Some(time) => {
+            logger::set_max_level(time.max_log_level);
+            // SAFETY: Wasm modules are single threaded
+            unsafe { TIME = time }
+        }
+        None => error("Failed to read time info sent by host"),
+    }
+}
+
+/// Time info of the current run.
+pub fn time() -> &'static TimeInfo {
+    // SAFETY: Wasm modules are single threaded
+    unsafe { &TIME }
+}
+
+/// Seconds since the previous run. Use it to make things frame-rate independent.
+pub fn delta_seconds() -> f64 {
+    time().delta_seconds
+}
+
+pub fn elapsed_seconds() -> f64 {
+    time().elapsed_seconds
+}
+
+pub fn frame_count() -> u64 {
+    time().frame_count
+}
+
+/// When true, [`delta_seconds`] is always the same, but mod may run more than once per frame.
+pub fn is_fixed_timestep() -> bool {
+    time().fixed_timestep
+}
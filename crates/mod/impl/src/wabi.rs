@@ -1,38 +1,70 @@
-use wabi_mod_api::{log::LogMessage, query::Filter, Action};
+use std::panic::Location;
 
-use crate::{io::send_action, query};
+use wabi_mod_api::{
+    error::ActionError,
+    log::{LogLevel, LogMessage},
+    query::Filter,
+    Action,
+};
 
+pub use wabi_mod_macros::{export, manifest, system};
+
+use crate::{io::send_action, logger, query};
+
+#[track_caller]
 pub fn trace(message: impl ToString) {
-    log::<0>(message.to_string());
+    log(LogLevel::Trace, message.to_string());
 }
 
+#[track_caller]
 pub fn debug(message: impl ToString) {
-    log::<1>(message.to_string());
+    log(LogLevel::Debug, message.to_string());
 }
 
+#[track_caller]
 pub fn info(message: impl ToString) {
-    log::<2>(message.to_string());
+    log(LogLevel::Info, message.to_string());
 }
 
+#[track_caller]
 pub fn warn(message: impl ToString) {
-    log::<3>(message.to_string());
+    log(LogLevel::Warn, message.to_string());
 }
 
+#[track_caller]
 pub fn error(message: impl ToString) {
-    log::<4>(message.to_string());
+    log(LogLevel::Error, message.to_string());
 }
 
-pub fn log<const L: u8>(message: String) {
-    send_action(&LogMessage { level: L, message }, Action::LOG);
-}
+#[track_caller]
+pub fn log(level: LogLevel, message: String) {
+    if !logger::is_enabled(level) {
+        return;
+    }
+
+    let location = Location::caller();
 
-#[no_mangle]
-pub extern "C" fn __wabi_entry_point() {
-    let result = query::query(
-        &["bevy_transform::components::transform::Transform"],
-        &[Filter::With("bevy_core::name::Name".to_string())],
+    send_action(
+        &LogMessage {
+            level,
+            message,
+            target: String::new(),
+            module_path: None,
+            file: Some(location.file().to_string()),
+            line: Some(location.line()),
+        },
+        Action::LOG,
     );
+}
+
+manifest!();
+
+#[system(reads("Transform", "Name"))]
+fn example() -> Result<(), ActionError> {
+    let result = query::query_dynamic(&["Transform"], &[Filter::With("Name".to_string())])?;
     trace(format!("Result: {:?}", result));
+
+    Ok(())
 }
 
 macro_rules! unwrap {
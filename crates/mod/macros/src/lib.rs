@@ -0,0 +1,249 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, AttributeArgs, FnArg, Ident, ItemFn, Lit, LitStr, Meta, NestedMeta,
+    ReturnType, Type,
+};
+
+mod manifest;
+
+/// Exports a function as a mod system, which is called by host every frame. A mod can have many
+/// systems, which run in the order they are exported.
+///
+/// The function must take no arguments and return either `()` or `Result<(), E>` where `E`
+/// implements `Display`. Errors are logged on host.
+///
+/// Components accessed by the system can be declared, so host knows about it:
+/// ```ignore
+/// #[wabi::system(name = "movement", reads("Velocity"), writes("Transform"))]
+/// fn movement() -> Result<(), QueryError> { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn system(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AttributeArgs);
+    let system = parse_macro_input!(item as ItemFn);
+
+    match expand_system(args, system) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Exports a function, so other mods can call it using `wabi_mod_impl::call::call`.
+///
+/// The function can take either no arguments or a single one, sent by the caller, and return any
+/// reflected value or `Result<T, E>` where `E` implements `Display`. Errors are sent back to caller.
+/// ```ignore
+/// #[wabi::export(name = "damage")]
+/// fn apply_damage(damage: Damage) -> Result<u32, ActionError> { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn export(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AttributeArgs);
+    let export = parse_macro_input!(item as ItemFn);
+
+    match expand_export(args, export) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Embeds the mod manifest on a wasm custom section, so host knows about the mod before running it.
+///
+/// Manifest is built from `[package]` and `[package.metadata.wabi]` sections of mod's `Cargo.toml`:
+/// ```toml
+/// [package.metadata.wabi]
+/// id = "my_mod"                         # Defaults to package name
+/// name = "My Mod"                       # Defaults to package name
+/// capabilities = ["query", "spawn"]
+///
+/// [package.metadata.wabi.dependencies]
+/// other_mod = "^0.2"
+/// ```
+#[proc_macro]
+pub fn manifest(input: TokenStream) -> TokenStream {
+    if !input.is_empty() {
+        return syn::Error::new_spanned(
+            TokenStream2::from(input),
+            "`manifest!` doesn't take arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    match manifest::expand_manifest() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_system(args: AttributeArgs, system: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &system.sig;
+
+    if !sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "Wabi systems can't have arguments",
+        ));
+    }
+
+    if sig.asyncness.is_some() || !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "Wabi systems can't be async nor generic",
+        ));
+    }
+
+    let ident = &sig.ident;
+    let mut name = LitStr::new(&ident.to_string(), ident.span());
+    let mut reads = vec![];
+    let mut writes = vec![];
+
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("name") => {
+                match meta.lit {
+                    Lit::Str(lit) => name = lit,
+                    lit => return Err(syn::Error::new_spanned(lit, "Expected a string literal")),
+                }
+            }
+            NestedMeta::Meta(Meta::List(list))
+                if list.path.is_ident("reads") || list.path.is_ident("writes") =>
+            {
+                let target = if list.path.is_ident("reads") {
+                    &mut reads
+                } else {
+                    &mut writes
+                };
+
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Lit(Lit::Str(lit)) => target.push(lit),
+                        other => {
+                            return Err(syn::Error::new_spanned(
+                                other,
+                                "Expected component names as string literals",
+                            ))
+                        }
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "Unknown argument. Expected `name = \"...\"`, `reads(...)` or `writes(...)`",
+                ))
+            }
+        }
+    }
+
+    let export_ident = format_ident!("__wabi_system_{}", ident);
+
+    Ok(quote! {
+        #system
+
+        #[no_mangle]
+        pub extern "C" fn #export_ident(len: u32) {
+            static SETUP: ::std::sync::Once = ::std::sync::Once::new();
+
+            ::wabi_mod_impl::system::run_system(
+                ::wabi_mod_impl::system::SystemDescriptor {
+                    name: #name,
+                    reads: &[#(#reads),*],
+                    writes: &[#(#writes),*],
+                },
+                &SETUP,
+                len,
+                #ident,
+            );
+        }
+    })
+}
+
+fn expand_export(args: AttributeArgs, export: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &export.sig;
+
+    if sig.asyncness.is_some() || !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "Exported functions can't be async nor generic",
+        ));
+    }
+
+    if sig.inputs.len() > 1 {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "Exported functions take at most one argument. Use a struct to receive more values",
+        ));
+    }
+
+    if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "Exported functions can't take self",
+        ));
+    }
+
+    let ident = &sig.ident;
+    let mut name = LitStr::new(&ident.to_string(), ident.span());
+
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("name") => {
+                match meta.lit {
+                    Lit::Str(lit) => name = lit,
+                    lit => return Err(syn::Error::new_spanned(lit, "Expected a string literal")),
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "Unknown argument. Expected `name = \"...\"`",
+                ))
+            }
+        }
+    }
+
+    if syn::parse_str::<Ident>(&name.value()).is_err() {
+        return Err(syn::Error::new_spanned(
+            &name,
+            "Export name must be a valid identifier",
+        ));
+    }
+
+    let export_ident = format_ident!("__wabi_export_{}", name.value());
+
+    let returns_result = match &sig.output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map_or(false, |segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    };
+
+    let runner = if returns_result {
+        quote!(run_export_result)
+    } else {
+        quote!(run_export)
+    };
+
+    let callee = if sig.inputs.is_empty() {
+        quote!(|(): ()| #ident())
+    } else {
+        quote!(#ident)
+    };
+
+    Ok(quote! {
+        #export
+
+        #[no_mangle]
+        pub extern "C" fn #export_ident(len: u32) -> u32 {
+            ::wabi_mod_impl::call::#runner(#name, len, #callee)
+        }
+    })
+}
@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+use bevy_reflect::serde::ReflectSerializer;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use toml::Value;
+use wabi_mod_api::{
+    capability::Capability,
+    manifest::{Dependency, ModManifest, API_VERSION, MANIFEST_SECTION},
+    registry::create_type_registry,
+};
+
+fn error(message: impl std::fmt::Display) -> syn::Error {
+    syn::Error::new(Span::call_site(), message)
+}
+
+fn get_str<'a>(table: Option<&'a Value>, key: &str) -> Option<&'a str> {
+    table
+        .and_then(|table| table.get(key))
+        .and_then(Value::as_str)
+}
+
+fn get_str_list(table: Option<&Value>, key: &str) -> syn::Result<Vec<String>> {
+    let value = match table.and_then(|table| table.get(key)) {
+        Some(value) => value,
+        None => return Ok(vec![]),
+    };
+
+    value
+        .as_array()
+        .and_then(|list| {
+            list.iter()
+                .map(|item| item.as_str().map(ToString::to_string))
+                .collect()
+        })
+        .ok_or_else(|| error(format!("`{}` must be a list of strings", key)))
+}
+
+fn get_dependencies(wabi: Option<&Value>) -> syn::Result<Vec<Dependency>> {
+    let value = match wabi.and_then(|wabi| wabi.get("dependencies")) {
+        Some(value) => value,
+        None => return Ok(vec![]),
+    };
+
+    let table = value
+        .as_table()
+        .ok_or_else(|| error("`dependencies` must be a table of `id = \"version\"`"))?;
+
+    table
+        .iter()
+        .map(|(id, version_req)| {
+            version_req
+                .as_str()
+                .map(|version_req| Dependency {
+                    id: id.clone(),
+                    version_req: version_req.to_string(),
+                })
+                .ok_or_else(|| error(format!("Version of dependency `{}` must be a string", id)))
+        })
+        .collect()
+}
+
+fn get_capabilities(wabi: Option<&Value>) -> syn::Result<Vec<String>> {
+    let capabilities = get_str_list(wabi, "capabilities")?;
+
+    if let Some(unknown) = capabilities
+        .iter()
+        .find(|name| Capability::from_name(name).is_none())
+    {
+        return Err(error(format!(
+            "Unknown capability `{}`. Available: {}",
+            unknown,
+            Capability::ALL.map(Capability::name).join(", ")
+        )));
+    }
+
+    Ok(capabilities)
+}
+
+fn manifest_path() -> syn::Result<PathBuf> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| error("CARGO_MANIFEST_DIR isn't set. Are you building with cargo?"))?;
+    Ok(PathBuf::from(dir).join("Cargo.toml"))
+}
+
+fn read_manifest(path: &Path) -> syn::Result<ModManifest> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| error(format!("Failed to read {}: {}", path.display(), err)))?;
+    let cargo = content
+        .parse::<Value>()
+        .map_err(|err| error(format!("Failed to parse {}: {}", path.display(), err)))?;
+
+    let package = cargo.get("package");
+    let wabi = package
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("wabi"));
+
+    let package_name =
+        get_str(package, "name").ok_or_else(|| error("Missing `name` on `[package]`"))?;
+    let id = get_str(wabi, "id").unwrap_or(package_name);
+
+    Ok(ModManifest {
+        id: id.to_string(),
+        name: get_str(wabi, "name").unwrap_or(package_name).to_string(),
+        version: get_str(package, "version").unwrap_or("0.0.0").to_string(),
+        authors: get_str_list(package, "authors")?,
+        api_version: API_VERSION.to_string(),
+        dependencies: get_dependencies(wabi)?,
+        capabilities: get_capabilities(wabi)?,
+    })
+}
+
+pub(crate) fn expand_manifest() -> syn::Result<TokenStream2> {
+    let path = manifest_path()?;
+    let manifest = read_manifest(&path)?;
+
+    let registry = create_type_registry();
+    let bytes = rmp_serde::encode::to_vec(&ReflectSerializer::new(&manifest, &registry))
+        .map_err(|err| error(format!("Failed to serialize manifest: {}", err)))?;
+    let len = bytes.len();
+    let path = path.to_string_lossy();
+
+    Ok(quote! {
+        // Makes cargo rebuild the mod when its manifest changes.
+        const _: &[u8] = include_bytes!(#path);
+
+
+        #[cfg_attr(target_arch = "wasm32", link_section = #MANIFEST_SECTION)]
+        #[used]
+        static __WABI_MANIFEST: [u8; #len] = [#(#bytes),*];
+    })
+}
@@ -1,4 +1,7 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+};
 
 pub mod mod_api {
     pub use wabi_mod_api::*;
@@ -6,8 +9,24 @@ pub mod mod_api {
 
 pub const WABI_MOODULE_NAME: &str = "wabi";
 pub const WABI_ALLOCATOR: &str = "__wabi_alloc";
-pub const WABI_ENTRY_POINT: &str = "__wabi_entry_point";
+/// Prefix of systems exported by mods with `#[wabi::system]`, followed by the function name.
+pub const WABI_SYSTEM_PREFIX: &str = "__wabi_system_";
 pub const WABI_PROCESS_ACTION: &str = "__wabi_process_action";
+/// Prefix of functions exported by mods with `#[wabi::export]`.
+pub const WABI_EXPORT_PREFIX: &str = "__wabi_export_";
+
+/// Settings of a single module, given by host when loading it.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleOptions {
+    /// Gives the module a WASI context. Backends without WASI support ignore it.
+    pub wasi: bool,
+    /// Directory preopened as read-only on WASI context, as `.`.
+    pub preopen_dir: Option<PathBuf>,
+    /// Directory where compiled modules are cached, when supported by the backend.
+    pub cache_dir: Option<PathBuf>,
+    /// Seed of random sources given to the module, like WASI `random_get`.
+    pub seed: u64,
+}
 
 pub enum InstanceState<T: WabiInstancePlatform> {
     None,
@@ -95,7 +114,16 @@ pub trait WabiInstancePlatform {
     fn id(&self) -> u32;
 
     fn run_alloc(&mut self);
-    fn run_main(&mut self);
+    /// Names of systems exported by the module, without [`WABI_SYSTEM_PREFIX`].
+    fn systems(&self) -> Vec<String>;
+
+    /// Runs a system, which receives the length of the data on the buffer.
+    /// Returns the trap message if the module traps or if there is no such system.
+    fn run_system(&mut self, name: &str, len: u32) -> Result<(), String>;
+
+    /// Calls a function exported by the module, which receives and returns the length of the data
+    /// on the buffer. Returns `None` if there is no such export, or the trap message if it traps.
+    fn call_export(&mut self, name: &str, len: u32) -> Option<Result<u32, String>>;
 
     fn read_buffer(&mut self, len: u32) -> &[u8];
     fn write_buffer(&mut self, buffer: &[u8]);
@@ -105,7 +133,8 @@ pub trait WabiRuntimePlatform {
     type ModuleInstance: WabiInstancePlatform;
 
     fn new(process_action: fn(u32, u32, u8) -> u32) -> Self;
-    fn load_module(&mut self, id: u32, buffer: &[u8]);
+    /// Loads a module. `name` is used only to give context on errors.
+    fn load_module(&mut self, id: u32, name: &str, buffer: &[u8], options: &ModuleOptions);
     fn start_running_instance(&mut self, id: u32) -> Self::ModuleInstance;
     fn finish_running_instance(&mut self, id: u32, instance: Self::ModuleInstance);
     fn get_instance(&mut self, id: u32) -> Option<&mut Self::ModuleInstance>;
@@ -6,8 +6,8 @@ use js_sys::{
     WebAssembly::{self, Memory},
 };
 use wabi_runtime_api::{
-    InstanceState, WabiInstancePlatform, WabiRuntimePlatform, WABI_ALLOCATOR, WABI_ENTRY_POINT,
-    WABI_MOODULE_NAME, WABI_PROCESS_ACTION,
+    InstanceState, ModuleOptions, WabiInstancePlatform, WabiRuntimePlatform, WABI_ALLOCATOR,
+    WABI_MOODULE_NAME, WABI_PROCESS_ACTION, WABI_SYSTEM_PREFIX,
 };
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::{spawn_local, JsFuture};
@@ -28,7 +28,9 @@ pub struct ModInstance {
     id: u32,
 
     alloc: Function,
-    entry_point: Function,
+    /// Exported systems, by name.
+    systems: Vec<(String, Function)>,
+    exports: js_sys::Object,
     memory: WebAssembly::Memory,
 
     buffer: Vec<u8>,
@@ -49,8 +51,35 @@ impl WabiInstancePlatform for ModInstance {
             .unwrap() as u32;
     }
 
-    fn run_main(&mut self) {
-        self.entry_point.call0(&JsValue::undefined()).unwrap();
+    fn systems(&self) -> Vec<String> {
+        self.systems.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    fn run_system(&mut self, name: &str, len: u32) -> Result<(), String> {
+        let (_, system) = self
+            .systems
+            .iter()
+            .find(|(system, _)| system == name)
+            .ok_or_else(|| format!("System {} not found", name))?;
+
+        system
+            .call1(&JsValue::undefined(), &JsValue::from(len))
+            .map(|_| ())
+            .map_err(|err| format!("{:?}", err))
+    }
+
+    fn call_export(&mut self, name: &str, len: u32) -> Option<Result<u32, String>> {
+        let func = Reflect::get(&self.exports, &name.into())
+            .ok()?
+            .dyn_into::<Function>()
+            .ok()?;
+
+        let result = func
+            .call1(&JsValue::undefined(), &JsValue::from(len))
+            .map(|len| len.as_f64().unwrap_or_default() as u32)
+            .map_err(|err| format!("{:?}", err));
+
+        Some(result)
     }
 
     fn read_buffer(&mut self, len: u32) -> &[u8] {
@@ -84,15 +113,24 @@ impl ModInstance {
             .dyn_into::<Function>()
             .unwrap();
 
-        let entry_point = Reflect::get(&instance.exports(), &WABI_ENTRY_POINT.into())
-            .unwrap()
-            .dyn_into::<Function>()
-            .unwrap();
+        let systems = js_sys::Object::keys(&instance.exports())
+            .iter()
+            .filter_map(|key| key.as_string())
+            .filter_map(|key| {
+                let name = key.strip_prefix(WABI_SYSTEM_PREFIX)?.to_string();
+                let func = Reflect::get(&instance.exports(), &key.into())
+                    .ok()?
+                    .dyn_into::<Function>()
+                    .ok()?;
+                Some((name, func))
+            })
+            .collect();
 
         Self {
             id,
             alloc,
-            entry_point,
+            systems,
+            exports: instance.exports(),
             memory,
             buffer: Default::default(),
             buffer_offset: 0,
@@ -141,7 +179,7 @@ impl WabiRuntimePlatform for WasmRuntime {
         Self
     }
 
-    fn load_module(&mut self, id: u32, buffer: &[u8]) {
+    fn load_module(&mut self, id: u32, _name: &str, buffer: &[u8], _options: &ModuleOptions) {
         let buffer = Vec::from(buffer);
 
         let window = web_sys::window().unwrap();
@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::{debug, warn};
+use sha2::{Digest, Sha256};
+use wasmtime::{Engine, Module};
+
+const CACHE_EXTENSION: &str = "cwasm";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Smallest valid module. Its artifact holds the wasmtime version, target and engine settings.
+const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+/// File stem of cached artifacts of the module, without the digest.
+fn module_prefix(name: &str) -> String {
+    // Mod ids are defined by mods, so only safe characters are kept.
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Artifacts are keyed by a digest of module bytes and of the engine, which covers wasmtime
+/// version and configuration, so changing any of those compiles the module again.
+fn cache_path(dir: &Path, engine_digest: &[u8], name: &str, buffer: &[u8]) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(engine_digest);
+    hasher.update(buffer);
+
+    dir.join(format!("{}-{:x}", module_prefix(name), hasher.finalize()))
+        .with_extension(CACHE_EXTENSION)
+}
+
+/// Removes artifacts of previous versions of the module, or of other engines, keeping `current`.
+fn remove_stale(name: &str, current: &Path) {
+    let (dir, prefix) = match current.parent() {
+        Some(dir) => (dir, module_prefix(name)),
+        None => return,
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let is_stale = path != current
+            && path.extension().and_then(|ext| ext.to_str()) == Some(CACHE_EXTENSION)
+            && path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.rsplit_once('-'))
+                .map_or(false, |(module, _)| module == prefix);
+
+        if is_stale {
+            if let Err(err) = fs::remove_file(&path) {
+                warn!("Failed to remove stale module {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+fn load(engine: &Engine, path: &Path) -> Option<Module> {
+    let bytes = fs::read(path).ok()?;
+
+    // SAFETY: Cache files are only written by `store`, using `Module::serialize`. Artifacts of
+    // other wasmtime versions or incompatible configurations are rejected by `deserialize`.
+    match unsafe { Module::deserialize(engine, bytes) } {
+        Ok(module) => Some(module),
+        Err(err) => {
+            warn!("Discarding cached module {}: {}", path.display(), err);
+            let _ = fs::remove_file(path);
+            None
+        }
+    }
+}
+
+/// Writes to a temporary file first, so an interrupted write doesn't leave a broken artifact.
+fn store(module: &Module, path: &Path) -> Result<(), String> {
+    let bytes = module.serialize().map_err(|err| err.to_string())?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+
+    let temp = path.with_extension(TEMP_EXTENSION);
+    fs::write(&temp, bytes).map_err(|err| err.to_string())?;
+    fs::rename(&temp, path).map_err(|err| err.to_string())
+}
+
+/// Compiles modules of a single engine, caching their artifacts when a directory is given.
+#[derive(Default)]
+pub(crate) struct ModuleCache {
+    /// Digest of the engine artifact. Computed on first cached load, since it compiles a module.
+    engine_digest: Option<Vec<u8>>,
+}
+
+impl ModuleCache {
+    fn engine_digest(&mut self, engine: &Engine) -> Result<&[u8], String> {
+        let digest = match self.engine_digest.take() {
+            Some(digest) => digest,
+            None => {
+                let artifact = engine
+                    .precompile_module(EMPTY_MODULE)
+                    .map_err(|err| err.to_string())?;
+                Sha256::digest(&artifact).to_vec()
+            }
+        };
+
+        Ok(self.engine_digest.insert(digest))
+    }
+
+    /// Loads the compiled module from `cache_dir` when available. Otherwise compiles it and
+    /// stores it there for the next time.
+    pub(crate) fn load_or_compile(
+        &mut self,
+        engine: &Engine,
+        name: &str,
+        buffer: &[u8],
+        cache_dir: Option<&Path>,
+    ) -> Result<Module, String> {
+        let dir = match cache_dir {
+            Some(dir) => dir,
+            None => return Module::from_binary(engine, buffer).map_err(|err| err.to_string()),
+        };
+
+        let path = match self.engine_digest(engine) {
+            Ok(engine_digest) => cache_path(dir, engine_digest, name, buffer),
+            Err(err) => {
+                warn!("Failed to get cache key of module {}: {}", name, err);
+                return Module::from_binary(engine, buffer).map_err(|err| err.to_string());
+            }
+        };
+
+        if let Some(module) = load(engine, &path) {
+            debug!("Module {} loaded from cache {}", name, path.display());
+            return Ok(module);
+        }
+
+        let module = Module::from_binary(engine, buffer).map_err(|err| err.to_string())?;
+
+        match store(&module, &path) {
+            Ok(()) => remove_stale(name, &path),
+            Err(err) => warn!(
+                "Failed to cache module {} on {}: {}",
+                name,
+                path.display(),
+                err
+            ),
+        }
+
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wabi_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn key_depends_on_engine_and_module() {
+        let dir = Path::new("cache");
+        let path = cache_path(dir, b"engine", "my mod", b"module");
+
+        assert_eq!(path, cache_path(dir, b"engine", "my mod", b"module"));
+        assert_ne!(path, cache_path(dir, b"other engine", "my mod", b"module"));
+        assert_ne!(path, cache_path(dir, b"engine", "my mod", b"other module"));
+
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap();
+        assert!(name.starts_with("my_mod-"));
+        assert!(name.ends_with(".cwasm"));
+    }
+
+    #[test]
+    fn remove_stale_keeps_current_and_other_modules() {
+        let dir = temp_dir("remove_stale");
+        let current = cache_path(&dir, b"engine", "module", b"v2");
+        let stale = cache_path(&dir, b"engine", "module", b"v1");
+        let other = cache_path(&dir, b"engine", "module_b", b"v1");
+        let unrelated = dir.join("module-0.txt");
+
+        for path in [&current, &stale, &other, &unrelated] {
+            fs::write(path, b"").unwrap();
+        }
+
+        remove_stale("module", &current);
+
+        assert!(current.exists());
+        assert!(!stale.exists());
+        assert!(other.exists());
+        assert!(unrelated.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn engine_digest_is_computed_once() {
+        let engine = Engine::default();
+        let mut cache = ModuleCache::default();
+
+        let digest = cache.engine_digest(&engine).unwrap().to_vec();
+        assert_eq!(cache.engine_digest.as_deref(), Some(&*digest));
+        assert_eq!(cache.engine_digest(&engine).unwrap(), digest);
+    }
+}
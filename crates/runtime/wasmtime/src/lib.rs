@@ -1,19 +1,51 @@
 use std::collections::HashMap;
 
-use bevy::prelude::error;
 use wabi_runtime_api::{
-    InstanceState, WabiInstancePlatform, WabiRuntimePlatform, WABI_ALLOCATOR, WABI_ENTRY_POINT,
-    WABI_MOODULE_NAME, WABI_PROCESS_ACTION,
+    InstanceState, ModuleOptions, WabiInstancePlatform, WabiRuntimePlatform, WABI_ALLOCATOR,
+    WABI_MOODULE_NAME, WABI_PROCESS_ACTION, WABI_SYSTEM_PREFIX,
 };
 use wasmtime::*;
 
+use crate::cache::ModuleCache;
+
+mod cache;
+#[cfg(feature = "wasi")]
+mod wasi;
+
+/// Data stored on each module [`Store`].
+pub struct ModuleData {
+    #[cfg(feature = "wasi")]
+    wasi: Option<wasmtime_wasi::WasiCtx>,
+}
+
+/// Reads an UTF-8 string from guest memory. Invalid UTF-8 sequences are replaced.
+fn read_string(caller: &mut Caller<'_, ModuleData>, ptr: i32, len: i32) -> Result<String, Trap> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("Module doesn't export a memory"))?;
+
+    let begin = ptr as u32 as usize;
+    let end = begin
+        .checked_add(len as u32 as usize)
+        .ok_or_else(|| Trap::new("String out of module memory bounds"))?;
+
+    memory
+        .data(&*caller)
+        .get(begin..end)
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .ok_or_else(|| Trap::new("String out of module memory bounds"))
+}
+
 pub struct WasmtimeInstance {
     id: u32,
 
     init: TypedFunc<u32, u32>,
-    main: TypedFunc<(), ()>,
+    /// Exported systems, by name.
+    systems: Vec<(String, TypedFunc<u32, ()>)>,
 
-    store: Store<()>,
+    instance: Instance,
+    store: Store<ModuleData>,
     memory: Memory,
 
     buffer_offset: u32,
@@ -28,10 +60,35 @@ impl WabiInstancePlatform for WasmtimeInstance {
         self.buffer_offset = self.init.call(&mut self.store, self.id).unwrap();
     }
 
-    fn run_main(&mut self) {
-        if let Err(err) = self.main.call(&mut self.store, ()) {
-            error!("Failed to run main: {}", err);
-        }
+    fn systems(&self) -> Vec<String> {
+        self.systems.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    fn run_system(&mut self, name: &str, len: u32) -> Result<(), String> {
+        let system = self
+            .systems
+            .iter()
+            .find(|(system, _)| system == name)
+            .map(|(_, func)| *func)
+            .ok_or_else(|| format!("System {} not found", name))?;
+
+        system
+            .call(&mut self.store, len)
+            .map_err(|trap| trap.to_string())
+    }
+
+    fn call_export(&mut self, name: &str, len: u32) -> Option<Result<u32, String>> {
+        let func = self.instance.get_func(&mut self.store, name)?;
+
+        let result = func
+            .typed::<u32, u32, _>(&self.store)
+            .map_err(|err| err.to_string())
+            .and_then(|func| {
+                func.call(&mut self.store, len)
+                    .map_err(|trap| trap.to_string())
+            });
+
+        Some(result)
     }
 
     fn read_buffer(&mut self, len: u32) -> &[u8] {
@@ -47,11 +104,44 @@ impl WabiInstancePlatform for WasmtimeInstance {
     }
 }
 
+/// Links host functions used by modules.
+fn create_linker(engine: &Engine, process_action: fn(u32, u32, u8) -> u32) -> Linker<ModuleData> {
+    let mut linker = Linker::new(engine);
+
+    linker
+        .func_wrap(
+            "wbg",
+            "__wbindgen_throw",
+            |mut caller: Caller<'_, ModuleData>, ptr: i32, len: i32| -> Result<(), Trap> {
+                // Logged by host along with the trap, once the module finishes running.
+                Err(Trap::new(read_string(&mut caller, ptr, len)?))
+            },
+        )
+        .unwrap();
+
+    linker
+        .func_wrap(
+            WABI_MOODULE_NAME,
+            WABI_PROCESS_ACTION,
+            move |_caller: Caller<'_, ModuleData>, id: u32, len: u32, action: u32| {
+                (process_action)(id, len, action as u8)
+            },
+        )
+        .unwrap();
+
+    linker
+}
+
 pub struct WasmtimeRuntime {
     engine: Engine,
-    linker: Linker<()>,
+    linker: Linker<ModuleData>,
+    /// Same as `linker`, plus WASI functions. Used only by modules with WASI context.
+    #[cfg(feature = "wasi")]
+    wasi_linker: Linker<ModuleData>,
 
     instances: HashMap<u32, InstanceState<WasmtimeInstance>>,
+    /// Shared by all modules, since they are compiled by the same engine.
+    cache: ModuleCache,
 }
 
 impl WabiRuntimePlatform for WasmtimeRuntime {
@@ -60,40 +150,65 @@ impl WabiRuntimePlatform for WasmtimeRuntime {
     fn new(process_action: fn(u32, u32, u8) -> u32) -> Self {
         let engine = Engine::default();
 
-        let mut linker = Linker::new(&engine);
+        let linker = create_linker(&engine, process_action);
 
-        linker
-            .func_wrap(
-                "wbg",
-                "__wbindgen_throw",
-                |_caller: Caller<'_, ()>, _ptr: i32, _len: i32| {
-                    error!("Mod is trying to throw an error, but it's not implemented yet");
-                },
-            )
-            .unwrap();
-
-        linker
-            .func_wrap(
-                WABI_MOODULE_NAME,
-                WABI_PROCESS_ACTION,
-                move |_caller: Caller<'_, ()>, id: u32, len: u32, action: u32| {
-                    (process_action)(id, len, action as u8)
-                },
-            )
-            .unwrap();
+        #[cfg(feature = "wasi")]
+        let wasi_linker = {
+            let mut linker = create_linker(&engine, process_action);
+            wasi::add_to_linker(&mut linker);
+            linker
+        };
 
         Self {
             engine,
             linker,
+            #[cfg(feature = "wasi")]
+            wasi_linker,
             instances: Default::default(),
+            cache: Default::default(),
         }
     }
 
-    fn load_module(&mut self, id: u32, buffer: &[u8]) {
-        let module = Module::from_binary(&self.engine, buffer).unwrap();
-        let mut store = Store::new(&self.engine, ());
+    fn load_module(&mut self, id: u32, name: &str, buffer: &[u8], options: &ModuleOptions) {
+        let module = self
+            .cache
+            .load_or_compile(&self.engine, name, buffer, options.cache_dir.as_deref())
+            .unwrap();
+        let mut store = Store::new(
+            &self.engine,
+            ModuleData {
+                #[cfg(feature = "wasi")]
+                wasi: options.wasi.then(|| {
+                    wasi::create_context(name, options.preopen_dir.as_deref(), options.seed)
+                }),
+            },
+        );
 
-        let instance = self.linker.instantiate(&mut store, &module).unwrap();
+        #[cfg(feature = "wasi")]
+        let linker = if options.wasi {
+            &self.wasi_linker
+        } else {
+            &self.linker
+        };
+
+        #[cfg(not(feature = "wasi"))]
+        let linker = {
+            if options.wasi {
+                bevy::prelude::warn!(
+                    "Module {} requested WASI, but wasmtime backend was built without it",
+                    name
+                );
+            }
+            &self.linker
+        };
+
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+
+        // Reactor modules, like those built for `wasm32-wasi`, must be initialized before any other
+        // export is called.
+        if let Ok(initialize) = instance.get_typed_func::<(), (), _>(&mut store, "_initialize") {
+            initialize.call(&mut store, ()).unwrap();
+        }
 
         let init = instance
             .get_func(&mut store, WABI_ALLOCATOR)
@@ -101,11 +216,18 @@ impl WabiRuntimePlatform for WasmtimeRuntime {
             .typed(&mut store)
             .unwrap();
 
-        let main = instance
-            .get_func(&mut store, WABI_ENTRY_POINT)
-            .unwrap()
-            .typed(&mut store)
-            .unwrap();
+        let systems = module
+            .exports()
+            .filter_map(|export| export.name().strip_prefix(WABI_SYSTEM_PREFIX))
+            .map(|system| {
+                let func = instance
+                    .get_func(&mut store, &format!("{}{}", WABI_SYSTEM_PREFIX, system))
+                    .unwrap()
+                    .typed(&mut store)
+                    .unwrap();
+                (system.to_string(), func)
+            })
+            .collect();
 
         let memory = instance.get_memory(&mut store, "memory").unwrap();
 
@@ -114,7 +236,8 @@ impl WabiRuntimePlatform for WasmtimeRuntime {
             InstanceState::Idle(WasmtimeInstance {
                 id,
                 init,
-                main,
+                systems,
+                instance,
                 memory,
                 store,
                 buffer_offset: 0,
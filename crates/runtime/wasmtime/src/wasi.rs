@@ -0,0 +1,127 @@
+use std::{io::Write, path::Path};
+
+use bevy::prelude::{error, info, warn};
+use rand::{rngs::StdRng, SeedableRng};
+use wasi_common::{dir::DirCaps, file::FileCaps, pipe::WritePipe};
+use wasmtime::Linker;
+use wasmtime_wasi::{
+    sync::{ambient_authority, dir::Dir, WasiCtxBuilder},
+    WasiCtx,
+};
+
+use crate::ModuleData;
+
+/// Writes each line written by a module on stdout or stderr to host log.
+struct LogWriter {
+    module: String,
+    stderr: bool,
+    line: Vec<u8>,
+}
+
+impl LogWriter {
+    fn new(module: &str, stderr: bool) -> Self {
+        Self {
+            module: module.to_string(),
+            stderr,
+            line: vec![],
+        }
+    }
+
+    fn log_line(&mut self) {
+        let line = String::from_utf8_lossy(&self.line);
+        let line = line.trim_end_matches('\r');
+
+        if self.stderr {
+            warn!("[{}] {}", self.module, line);
+        } else {
+            info!("[{}] {}", self.module, line);
+        }
+
+        self.line.clear();
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &byte in buf {
+            if byte == b'\n' {
+                self.log_line();
+            } else {
+                self.line.push(byte);
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.line.is_empty() {
+            self.log_line();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LogWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+pub(crate) fn add_to_linker(linker: &mut Linker<ModuleData>) {
+    wasmtime_wasi::add_to_linker(linker, |data: &mut ModuleData| {
+        data.wasi
+            .as_mut()
+            .expect("WASI linker is only used by modules with WASI context")
+    })
+    .unwrap();
+}
+
+fn preopen_read_only(ctx: &mut WasiCtx, path: &Path) -> Result<(), String> {
+    let dir = wasmtime_wasi::sync::Dir::open_ambient_dir(path, ambient_authority())
+        .map_err(|err| err.to_string())?;
+
+    let dir_caps = DirCaps::OPEN
+        | DirCaps::READDIR
+        | DirCaps::READLINK
+        | DirCaps::PATH_FILESTAT_GET
+        | DirCaps::FILESTAT_GET;
+    let file_caps = FileCaps::READ
+        | FileCaps::SEEK
+        | FileCaps::TELL
+        | FileCaps::FILESTAT_GET
+        | FileCaps::POLL_READWRITE;
+
+    ctx.push_dir(
+        Box::new(Dir::from_cap_std(dir)),
+        dir_caps,
+        file_caps,
+        ".".into(),
+    )
+    .map(|_| ())
+    .map_err(|err| err.to_string())
+}
+
+/// Creates a WASI context which sends stdout and stderr to host log. There is no stdin and
+/// module can only read files inside `preopen_dir`, if any. Random numbers come from `seed`, so
+/// they are the same when the session is replayed.
+pub(crate) fn create_context(module: &str, preopen_dir: Option<&Path>, seed: u64) -> WasiCtx {
+    let mut ctx = WasiCtxBuilder::new()
+        .stdout(Box::new(WritePipe::new(LogWriter::new(module, false))))
+        .stderr(Box::new(WritePipe::new(LogWriter::new(module, true))))
+        .build();
+    ctx.random = Box::new(StdRng::seed_from_u64(seed));
+
+    if let Some(path) = preopen_dir {
+        if let Err(err) = preopen_read_only(&mut ctx, path) {
+            error!(
+                "Failed to preopen {} for module {}: {}",
+                path.display(),
+                module,
+                err
+            );
+        }
+    }
+
+    ctx
+}
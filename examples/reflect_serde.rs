@@ -20,6 +20,8 @@ fn main() {
         items: vec![QueryFetchItem {
             entity: Default::default(),
             components: vec![component_struct, simple_enum],
+            optional: vec![],
+            fields: vec![],
         }],
     };
 
@@ -15,6 +15,8 @@ fn main() {
         items: vec![QueryFetchItem {
             entity: Default::default(),
             components: vec![component],
+            optional: vec![],
+            fields: vec![],
         }],
     };
 
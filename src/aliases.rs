@@ -0,0 +1,33 @@
+use bevy::{
+    prelude::{App, Resource},
+    utils::HashMap,
+};
+
+/// Alternative names which mods can use to refer to components, mapped to the component full
+/// type name. Useful to keep mods working when a type is moved or renamed.
+#[derive(Resource, Default, Debug)]
+pub struct ComponentAliases(HashMap<String, String>);
+
+impl ComponentAliases {
+    pub fn insert(&mut self, alias: impl ToString, name: impl ToString) {
+        self.0.insert(alias.to_string(), name.to_string());
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&str> {
+        self.0.get(alias).map(String::as_str)
+    }
+}
+
+pub trait RegisterComponentAlias {
+    fn register_component_alias(&mut self, alias: impl ToString, name: impl ToString) -> &mut Self;
+}
+
+impl RegisterComponentAlias for App {
+    fn register_component_alias(&mut self, alias: impl ToString, name: impl ToString) -> &mut Self {
+        self.init_resource::<ComponentAliases>();
+        self.world
+            .resource_mut::<ComponentAliases>()
+            .insert(alias, name);
+        self
+    }
+}
@@ -1,16 +1,55 @@
+use std::path::{Path, PathBuf};
+
 use bevy::{
     asset::{AssetLoader, LoadedAsset},
     // prelude::{AssetEvent, EventReader},
+    prelude::warn,
     reflect::{Reflect, TypeUuid},
 };
+use bevy_reflect::{
+    erased_serde::__private::serde::de::DeserializeSeed, serde::UntypedReflectDeserializer,
+    FromReflect,
+};
+use wabi_runtime_api::mod_api::{
+    manifest::{ModManifest, MANIFEST_SECTION},
+    registry::create_type_registry,
+};
+use wasmparser::{Parser, Payload};
 
 #[derive(Debug, Default, TypeUuid, Reflect)]
 #[uuid = "44ceeab1-69e2-4afb-b0e2-7d97d8d0bdda"]
 pub struct WasmAsset {
+    /// Manifest id, or the file stem when module has no manifest.
     pub name: String,
+    pub manifest: Option<ModManifest>,
+    /// Directory of the module, relative to assets folder. Assets loaded by the module are
+    /// relative to it.
+    pub dir: PathBuf,
     pub(crate) buffer: Vec<u8>,
 }
 
+/// Reads the manifest embedded by `wabi::manifest!()` on the module custom section.
+fn read_manifest(bytes: &[u8]) -> Result<Option<ModManifest>, bevy::asset::Error> {
+    for payload in Parser::new(0).parse_all(bytes) {
+        let section = match payload? {
+            Payload::CustomSection(section) if section.name() == MANIFEST_SECTION => section,
+            _ => continue,
+        };
+
+        let registry = create_type_registry();
+        let reflect_deserializer = UntypedReflectDeserializer::new(&registry);
+        let mut deserializer = rmp_serde::Deserializer::from_read_ref(section.data());
+
+        let manifest = reflect_deserializer.deserialize(&mut deserializer)?;
+
+        return ModManifest::from_reflect(&*manifest)
+            .map(Some)
+            .ok_or_else(|| bevy::asset::Error::msg("Invalid module manifest"));
+    }
+
+    Ok(None)
+}
+
 impl AssetLoader for WasmAsset {
     fn load<'a>(
         &'a self,
@@ -18,13 +57,32 @@ impl AssetLoader for WasmAsset {
         load_context: &'a mut bevy::asset::LoadContext,
     ) -> bevy::utils::BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
+            let manifest = read_manifest(bytes)?;
+
+            let name = match &manifest {
+                Some(manifest) => manifest.id.clone(),
+                None => {
+                    let name = load_context
+                        .path()
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string();
+                    warn!("Module {} has no manifest", name);
+                    name
+                }
+            };
+
+            let dir = load_context
+                .path()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+
             load_context.set_default_asset(LoadedAsset::new(WasmAsset {
-                name: load_context
-                    .path()
-                    .file_stem()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
+                name,
+                manifest,
+                dir,
                 buffer: Vec::from(bytes),
             }));
             Ok(())
@@ -34,4 +92,4 @@ impl AssetLoader for WasmAsset {
     fn extensions(&self) -> &[&str] {
         &["wasm"]
     }
-}
\ No newline at end of file
+}
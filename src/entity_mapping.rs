@@ -0,0 +1,91 @@
+use bevy::prelude::{Children, Entity as HostEntity, Parent, World};
+use bevy_reflect::Reflect;
+use wabi_runtime_api::mod_api::{ecs::Entity, error::ActionError, hierarchy};
+
+pub(crate) fn to_mod_entity(entity: HostEntity) -> Entity {
+    Entity {
+        id: entity.id(),
+        generation: entity.generation(),
+    }
+}
+
+/// Checks if the entity sent by mod still exists. Entities are only valid while their generation
+/// matches, since despawned entity indices are reused by new entities.
+pub(crate) fn to_host_entity(world: &World, entity: Entity) -> Result<HostEntity, ActionError> {
+    let host_entity = HostEntity::from_bits((entity.generation as u64) << 32 | entity.id as u64);
+    let entities = world.entities();
+
+    if entities.get(host_entity).is_some() {
+        return Ok(host_entity);
+    }
+
+    match entities.resolve_from_id(entity.id) {
+        Some(current) if current.generation() != entity.generation => {
+            Err(ActionError::StaleEntity {
+                entity,
+                current_generation: current.generation(),
+            })
+        }
+        _ => Err(ActionError::EntityNotFound(entity)),
+    }
+}
+
+/// Converts host values which holds entities to their mod side representation, since mods
+/// doesn't know how to deserialize host entities. Other values are just cloned, so entities
+/// nested inside other values aren't translated.
+pub(crate) fn to_mod_value(value: &dyn Reflect) -> Box<dyn Reflect> {
+    if let Some(entity) = value.downcast_ref::<HostEntity>() {
+        Box::new(to_mod_entity(*entity))
+    } else if let Some(parent) = value.downcast_ref::<Parent>() {
+        Box::new(hierarchy::Parent(to_mod_entity(parent.get())))
+    } else if let Some(children) = value.downcast_ref::<Children>() {
+        Box::new(hierarchy::Children(
+            children.iter().copied().map(to_mod_entity).collect(),
+        ))
+    } else {
+        value.clone_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_entity() {
+        let mut world = World::new();
+        let entity = world.spawn().id();
+
+        let result = to_host_entity(&world, to_mod_entity(entity));
+        assert_eq!(result.ok(), Some(entity));
+    }
+
+    #[test]
+    fn respawned_index_is_stale() {
+        let mut world = World::new();
+        let despawned = world.spawn().id();
+        world.despawn(despawned);
+        let respawned = world.spawn().id();
+        assert_eq!(respawned.id(), despawned.id());
+
+        let result = to_host_entity(&world, to_mod_entity(despawned));
+        assert!(matches!(
+            result,
+            Err(ActionError::StaleEntity { entity, current_generation })
+                if entity.id == despawned.id() && current_generation == respawned.generation()
+        ));
+    }
+
+    #[test]
+    fn unknown_index() {
+        let mut world = World::new();
+        world.spawn();
+
+        let entity = Entity {
+            id: 1000,
+            generation: 0,
+        };
+        let result = to_host_entity(&world, entity);
+        assert!(matches!(result, Err(ActionError::EntityNotFound(entity)) if entity.id == 1000));
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::{BuildWorldChildren, Children, Entity as HostEntity, Parent, World};
+use wabi_runtime_api::mod_api::{
+    ecs::EntityList,
+    error::ActionError,
+    hierarchy::{GetRelatives, Relation, SetParent},
+};
+
+use crate::entity_mapping::{to_host_entity, to_mod_entity};
+
+fn push_descendants(world: &World, entity: HostEntity, entities: &mut Vec<HostEntity>) {
+    if let Some(children) = world.get::<Children>(entity) {
+        for &child in children.iter() {
+            entities.push(child);
+            push_descendants(world, child, entities);
+        }
+    }
+}
+
+fn is_ancestor(world: &World, ancestor: HostEntity, entity: HostEntity) -> bool {
+    let mut current = entity;
+    while let Some(parent) = world.get::<Parent>(current) {
+        current = parent.get();
+        if current == ancestor {
+            return true;
+        }
+    }
+    false
+}
+
+pub(crate) fn get_relatives(
+    world: &World,
+    request: GetRelatives,
+) -> Result<EntityList, ActionError> {
+    let entity = to_host_entity(world, request.entity)?;
+
+    let mut entities = vec![];
+
+    match request.relation {
+        Relation::Parent => entities.extend(world.get::<Parent>(entity).map(Parent::get)),
+        Relation::Children => {
+            if let Some(children) = world.get::<Children>(entity) {
+                entities.extend(children.iter().copied());
+            }
+        }
+        Relation::Ancestors => {
+            let mut current = entity;
+            while let Some(parent) = world.get::<Parent>(current) {
+                current = parent.get();
+                entities.push(current);
+            }
+        }
+        Relation::Descendants => push_descendants(world, entity, &mut entities),
+    }
+
+    Ok(EntityList {
+        entities: entities.into_iter().map(to_mod_entity).collect(),
+    })
+}
+
+pub(crate) fn set_parent(world: &mut World, request: SetParent) -> Result<(), ActionError> {
+    let entity = to_host_entity(world, request.entity)?;
+    let parent = request
+        .parent
+        .map(|parent| to_host_entity(world, parent))
+        .transpose()?;
+
+    if let Some(parent) = parent {
+        if parent == entity || is_ancestor(world, entity, parent) {
+            return Err(ActionError::InvalidHierarchy(format!(
+                "Entity {:?} can't be a child of itself or of its descendants",
+                entity
+            )));
+        }
+
+        world.entity_mut(parent).push_children(&[entity]);
+    } else if let Some(old_parent) = world.get::<Parent>(entity).map(Parent::get) {
+        world.entity_mut(old_parent).remove_children(&[entity]);
+    }
+
+    Ok(())
+}
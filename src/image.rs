@@ -0,0 +1,141 @@
+use bevy::{
+    prelude::{Image, World},
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::TextureFormatPixelInfo,
+    },
+};
+use wabi_runtime_api::mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    image::{CreateImage, ImageFormat, UpdateImage},
+};
+
+use crate::mod_assets;
+
+fn to_texture_format(format: ImageFormat) -> TextureFormat {
+    match format {
+        ImageFormat::R8Unorm => TextureFormat::R8Unorm,
+        ImageFormat::Rg8Unorm => TextureFormat::Rg8Unorm,
+        ImageFormat::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        ImageFormat::Rgba8UnormSrgb => TextureFormat::Rgba8UnormSrgb,
+        ImageFormat::R32Float => TextureFormat::R32Float,
+        ImageFormat::Rgba32Float => TextureFormat::Rgba32Float,
+    }
+}
+
+/// Checks if `data` has exactly `width * height` pixels.
+fn validate_data(
+    width: u32,
+    height: u32,
+    pixel_size: usize,
+    data: &[u8],
+) -> Result<(), ActionError> {
+    if width == 0 || height == 0 {
+        return Err(ActionError::InvalidImage(format!(
+            "Size must not be zero, got {}x{}",
+            width, height
+        )));
+    }
+
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(pixel_size))
+        .ok_or_else(|| {
+            ActionError::InvalidImage(format!("Size {}x{} is too big", width, height))
+        })?;
+
+    if data.len() != expected {
+        return Err(ActionError::InvalidImage(format!(
+            "Expected {} bytes for {}x{} pixels, got {}",
+            expected,
+            width,
+            height,
+            data.len()
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn create(
+    world: &mut World,
+    module: &str,
+    CreateImage {
+        width,
+        height,
+        format,
+        data,
+    }: CreateImage,
+) -> Result<AssetHandle, ActionError> {
+    validate_data(width, height, format.pixel_size(), &data)?;
+
+    let image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        to_texture_format(format),
+    );
+
+    mod_assets::add(world, module, image)
+}
+
+/// Copies the region row by row into image data. Changing the asset makes the texture be
+/// uploaded again, so materials using it are updated.
+pub(crate) fn update(
+    world: &mut World,
+    UpdateImage {
+        handle,
+        x,
+        y,
+        width,
+        height,
+        data,
+    }: UpdateImage,
+) -> Result<(), ActionError> {
+    let image = mod_assets::get_mut::<Image>(world, handle)?;
+
+    let descriptor = &image.texture_descriptor;
+    let size = descriptor.size;
+
+    // Only plain 2D images can be updated pixel by pixel, unlike compressed or 3D ones.
+    if descriptor.dimension != TextureDimension::D2 || size.depth_or_array_layers != 1 {
+        return Err(ActionError::InvalidImage(format!(
+            "Only 2D images with a single layer can be updated, got {:?} with {} layers",
+            descriptor.dimension, size.depth_or_array_layers
+        )));
+    }
+
+    if descriptor.format.describe().block_dimensions != (1, 1) {
+        return Err(ActionError::InvalidImage(format!(
+            "Images with compressed format {:?} can't be updated",
+            descriptor.format
+        )));
+    }
+
+    let pixel_size = descriptor.format.pixel_size();
+
+    validate_data(width, height, pixel_size, &data)?;
+
+    if x as u64 + width as u64 > size.width as u64 || y as u64 + height as u64 > size.height as u64
+    {
+        return Err(ActionError::InvalidImage(format!(
+            "Region {}x{} at ({}, {}) is out of bounds of image {}x{}",
+            width, height, x, y, size.width, size.height
+        )));
+    }
+
+    let image_row = size.width as usize * pixel_size;
+    let region_row = width as usize * pixel_size;
+
+    for (row, pixels) in data.chunks_exact(region_row).enumerate() {
+        let start = (y as usize + row) * image_row + x as usize * pixel_size;
+        image.data[start..start + region_row].copy_from_slice(pixels);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::{fmt::Debug, hash::Hash};
+
+use bevy::{
+    input::{
+        gamepad::{Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, Gamepads},
+        Input,
+    },
+    prelude::{Axis, KeyCode, MouseButton, World},
+    window::Windows,
+};
+use wabi_runtime_api::mod_api::{
+    error::ActionError,
+    input::{AxisValue, ButtonState, GamepadState, InputState},
+};
+
+const GAMEPAD_AXES: [GamepadAxisType; 6] = [
+    GamepadAxisType::LeftStickX,
+    GamepadAxisType::LeftStickY,
+    GamepadAxisType::LeftZ,
+    GamepadAxisType::RightStickX,
+    GamepadAxisType::RightStickY,
+    GamepadAxisType::RightZ,
+];
+
+fn names<'a, T: Debug + 'a>(buttons: impl Iterator<Item = &'a T>) -> Vec<String> {
+    buttons.map(|button| format!("{:?}", button)).collect()
+}
+
+fn button_state<T>(world: &World) -> ButtonState
+where
+    T: Copy + Eq + Hash + Debug + Send + Sync + 'static,
+{
+    match world.get_resource::<Input<T>>() {
+        Some(input) => ButtonState {
+            pressed: names(input.get_pressed()),
+            just_pressed: names(input.get_just_pressed()),
+            just_released: names(input.get_just_released()),
+        },
+        None => Default::default(),
+    }
+}
+
+fn gamepad_buttons<'a>(
+    buttons: impl Iterator<Item = &'a GamepadButton>,
+    gamepad: Gamepad,
+) -> Vec<String> {
+    buttons
+        .filter(|button| button.gamepad == gamepad)
+        .map(|button| format!("{:?}", button.button_type))
+        .collect()
+}
+
+fn gamepad_state(world: &World, gamepad: Gamepad) -> GamepadState {
+    let buttons = match world.get_resource::<Input<GamepadButton>>() {
+        Some(input) => ButtonState {
+            pressed: gamepad_buttons(input.get_pressed(), gamepad),
+            just_pressed: gamepad_buttons(input.get_just_pressed(), gamepad),
+            just_released: gamepad_buttons(input.get_just_released(), gamepad),
+        },
+        None => Default::default(),
+    };
+
+    let axes = match world.get_resource::<Axis<GamepadAxis>>() {
+        Some(axis) => GAMEPAD_AXES
+            .iter()
+            .filter_map(|&axis_type| {
+                axis.get(GamepadAxis { gamepad, axis_type })
+                    .map(|value| AxisValue {
+                        axis: format!("{:?}", axis_type),
+                        value,
+                    })
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    GamepadState {
+        id: gamepad.id,
+        buttons,
+        axes,
+    }
+}
+
+/// Reads input resources, when available, so it works on headless apps too.
+pub(crate) fn get_input(world: &World) -> Result<InputState, ActionError> {
+    let cursor_position = world
+        .get_resource::<Windows>()
+        .and_then(|windows| windows.get_primary())
+        .and_then(|window| window.cursor_position());
+
+    let gamepads = world
+        .get_resource::<Gamepads>()
+        .map(|gamepads| {
+            gamepads
+                .iter()
+                .map(|gamepad| gamepad_state(world, gamepad))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(InputState {
+        keys: button_state::<KeyCode>(world),
+        mouse_buttons: button_state::<MouseButton>(world),
+        cursor_position,
+        gamepads,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{
+            event::Events,
+            system::{IntoSystem, System},
+        },
+        input::gamepad::{
+            gamepad_connection_system, GamepadButtonType, GamepadEvent, GamepadEventType,
+            GamepadInfo,
+        },
+    };
+
+    use super::*;
+
+    /// Gamepads are only registered by Bevy, when it receives a connection event.
+    fn connect_gamepad(world: &mut World, gamepad: Gamepad) {
+        world.init_resource::<Gamepads>();
+        world.init_resource::<Events<GamepadEvent>>();
+        world
+            .resource_mut::<Events<GamepadEvent>>()
+            .send(GamepadEvent::new(
+                gamepad,
+                GamepadEventType::Connected(GamepadInfo {
+                    name: "Test gamepad".to_string(),
+                }),
+            ));
+
+        let mut system = IntoSystem::into_system(gamepad_connection_system);
+        system.initialize(world);
+        system.run((), world);
+    }
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn get_input_without_resources() {
+        let input = get_input(&World::new()).unwrap();
+
+        assert!(input.keys.pressed.is_empty());
+        assert!(input.mouse_buttons.pressed.is_empty());
+        assert!(input.cursor_position.is_none());
+        assert!(input.gamepads.is_empty());
+    }
+
+    #[test]
+    fn get_input_reads_resources() {
+        let mut world = World::new();
+        let gamepad = Gamepad::new(0);
+        connect_gamepad(&mut world, gamepad);
+
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::A);
+        keys.press(KeyCode::B);
+        keys.clear();
+        keys.press(KeyCode::Space);
+        keys.release(KeyCode::B);
+        world.insert_resource(keys);
+
+        let mut buttons = Input::<GamepadButton>::default();
+        buttons.press(GamepadButton::new(gamepad, GamepadButtonType::South));
+        // Not connected, so it isn't reported.
+        buttons.press(GamepadButton::new(
+            Gamepad::new(1),
+            GamepadButtonType::North,
+        ));
+        world.insert_resource(buttons);
+
+        let mut axis = Axis::<GamepadAxis>::default();
+        axis.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), 0.5);
+        world.insert_resource(axis);
+
+        let input = get_input(&world).unwrap();
+
+        assert_eq!(sorted(input.keys.pressed), ["A", "Space"]);
+        assert_eq!(input.keys.just_pressed, ["Space"]);
+        assert_eq!(input.keys.just_released, ["B"]);
+        assert!(input.mouse_buttons.pressed.is_empty());
+        assert!(input.cursor_position.is_none());
+
+        assert_eq!(input.gamepads.len(), 1);
+        let state = &input.gamepads[0];
+        assert_eq!(state.id, 0);
+        assert_eq!(state.buttons.pressed, ["South"]);
+        assert!(state.buttons.just_released.is_empty());
+        assert_eq!(state.axes.len(), 1);
+        assert_eq!(state.axes[0].axis, "LeftStickX");
+        assert_eq!(state.axes[0].value, 0.5);
+    }
+}
@@ -5,11 +5,23 @@ use bevy::{
     prelude::*,
 };
 
-use runtime::RuntimePlugin;
+use runtime::{ModHandles, RuntimePlugin};
 
+mod aliases;
 mod asset;
+mod entity_mapping;
+mod hierarchy;
+mod image;
+mod input;
+mod material;
+mod mesh;
+mod mod_assets;
+mod permissions;
+mod reflect_commands;
 mod reflect_query;
 mod runtime;
+mod seed;
+mod storage;
 
 fn main() {
     App::new()
@@ -30,11 +42,8 @@ fn main() {
         .run();
 }
 
-#[derive(Resource, Component, Reflect, Debug, Default, Deref, DerefMut)]
-struct WasmHandler(pub Handle<WasmAsset>);
-
 fn pre_startup(asset_server: Res<AssetServer>, mut commands: Commands) {
-    commands.insert_resource(WasmHandler(asset_server.load("mods/impl.wasm")));
+    commands.insert_resource(ModHandles(vec![asset_server.load("mods/impl.wasm")]));
 }
 
 /// set up a simple 3D scene
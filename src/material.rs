@@ -0,0 +1,127 @@
+use bevy::prelude::{Color, Handle, Image, StandardMaterial, Vec3, Vec4, World};
+use bevy_reflect::{FromReflect, GetPath, Reflect, TypePath};
+use wabi_runtime_api::mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    material::{CreateMaterial, MaterialField, PatchMaterial},
+};
+
+use crate::mod_assets::{self, ModAssets};
+
+enum FieldValue<'a> {
+    Texture(Option<Handle<Image>>),
+    Reflect(&'a dyn Reflect),
+}
+
+/// Resolves asset handles before touching the material, since it's borrowed from the world.
+fn resolve_fields<'a>(
+    world: &World,
+    fields: &'a [MaterialField],
+) -> Result<Vec<(&'a str, FieldValue<'a>)>, ActionError> {
+    let assets = world.resource::<ModAssets>();
+
+    fields
+        .iter()
+        .map(|field| {
+            let value = field.value.as_reflect();
+            let type_path = value.type_path();
+
+            let value = if type_path == <AssetHandle as TypePath>::type_path() {
+                let handle = AssetHandle::from_reflect(value).unwrap_or_default();
+                FieldValue::Texture(Some(assets.get_typed::<Image>(handle)?))
+            } else if type_path == <Option<AssetHandle> as TypePath>::type_path() {
+                match Option::<AssetHandle>::from_reflect(value).flatten() {
+                    Some(handle) => FieldValue::Texture(Some(assets.get_typed::<Image>(handle)?)),
+                    None => FieldValue::Texture(None),
+                }
+            } else {
+                FieldValue::Reflect(value)
+            };
+
+            Ok((field.path.as_str(), value))
+        })
+        .collect()
+}
+
+fn to_color(value: &dyn Reflect) -> Option<Color> {
+    Vec4::from_reflect(value)
+        .map(|v| Color::rgba(v.x, v.y, v.z, v.w))
+        .or_else(|| Vec3::from_reflect(value).map(|v| Color::rgb(v.x, v.y, v.z)))
+}
+
+fn apply_field(
+    material: &mut StandardMaterial,
+    path: &str,
+    value: FieldValue,
+) -> Result<(), ActionError> {
+    let invalid = |reason: String| ActionError::InvalidPath {
+        component: "StandardMaterial".to_string(),
+        path: path.to_string(),
+        reason,
+    };
+
+    let target = material
+        .path_mut(path)
+        .map_err(|err| invalid(err.to_string()))?;
+
+    match value {
+        FieldValue::Texture(texture) => {
+            *target
+                .downcast_mut::<Option<Handle<Image>>>()
+                .ok_or_else(|| invalid("Field isn't a texture".to_string()))? = texture;
+        }
+        FieldValue::Reflect(value) => {
+            if let Some(color) = target.downcast_mut::<Color>() {
+                *color = to_color(value).ok_or_else(|| {
+                    invalid(format!("Expected Vec4 or Vec3, got {}", value.type_path()))
+                })?;
+            } else if target.type_path() == value.type_path() {
+                target.apply(value);
+            } else {
+                return Err(invalid(format!(
+                    "Expected {}, got {}",
+                    target.type_path(),
+                    value.type_path()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_fields(
+    material: &mut StandardMaterial,
+    fields: Vec<(&str, FieldValue)>,
+) -> Result<(), ActionError> {
+    for (path, value) in fields {
+        apply_field(material, path, value)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn create(
+    world: &mut World,
+    module: &str,
+    CreateMaterial { fields }: CreateMaterial,
+) -> Result<AssetHandle, ActionError> {
+    let mut material = StandardMaterial::default();
+    apply_fields(&mut material, resolve_fields(world, &fields)?)?;
+
+    mod_assets::add(world, module, material)
+}
+
+/// Fields are applied on a copy of the material, so it's only changed if all fields are valid.
+pub(crate) fn patch(
+    world: &mut World,
+    PatchMaterial { handle, fields }: PatchMaterial,
+) -> Result<(), ActionError> {
+    let fields = resolve_fields(world, &fields)?;
+
+    let mut material = mod_assets::get_mut::<StandardMaterial>(world, handle)?.clone();
+    apply_fields(&mut material, fields)?;
+
+    *mod_assets::get_mut::<StandardMaterial>(world, handle)? = material;
+
+    Ok(())
+}
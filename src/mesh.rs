@@ -0,0 +1,233 @@
+use bevy::{
+    prelude::{Mesh, World},
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use wabi_runtime_api::mod_api::{
+    asset::AssetHandle,
+    error::ActionError,
+    mesh::{CreateMesh, MeshData, MeshTopology, UpdateMesh},
+};
+
+use crate::mod_assets;
+
+fn to_topology(topology: MeshTopology) -> PrimitiveTopology {
+    match topology {
+        MeshTopology::PointList => PrimitiveTopology::PointList,
+        MeshTopology::LineList => PrimitiveTopology::LineList,
+        MeshTopology::LineStrip => PrimitiveTopology::LineStrip,
+        MeshTopology::TriangleList => PrimitiveTopology::TriangleList,
+        MeshTopology::TriangleStrip => PrimitiveTopology::TriangleStrip,
+    }
+}
+
+/// Checks if the number of indices, or vertices on meshes without indices, makes whole primitives.
+fn validate_count(topology: MeshTopology, count: usize, kind: &str) -> Result<(), ActionError> {
+    let valid = match topology {
+        MeshTopology::PointList => true,
+        MeshTopology::LineList => count % 2 == 0,
+        MeshTopology::LineStrip => count != 1,
+        MeshTopology::TriangleList => count % 3 == 0,
+        MeshTopology::TriangleStrip => count == 0 || count >= 3,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ActionError::InvalidMesh(format!(
+            "{} {} don't make whole primitives of {:?}",
+            count, kind, topology
+        )))
+    }
+}
+
+fn build_mesh(data: MeshData) -> Result<Mesh, ActionError> {
+    let MeshData {
+        topology,
+        positions,
+        normals,
+        uvs,
+        indices,
+    } = data;
+
+    let vertex_count = positions.len();
+
+    if !normals.is_empty() && normals.len() != vertex_count {
+        return Err(ActionError::InvalidMesh(format!(
+            "Expected {} normals, got {}",
+            vertex_count,
+            normals.len()
+        )));
+    }
+
+    if !uvs.is_empty() && uvs.len() != vertex_count {
+        return Err(ActionError::InvalidMesh(format!(
+            "Expected {} uvs, got {}",
+            vertex_count,
+            uvs.len()
+        )));
+    }
+
+    if let Some(index) = indices
+        .iter()
+        .flatten()
+        .find(|&&index| index as usize >= vertex_count)
+    {
+        return Err(ActionError::InvalidMesh(format!(
+            "Index {} is out of bounds, there are {} vertices",
+            index, vertex_count
+        )));
+    }
+
+    match &indices {
+        Some(indices) => validate_count(topology, indices.len(), "indices")?,
+        None => validate_count(topology, vertex_count, "vertices")?,
+    }
+
+    let mut mesh = Mesh::new(to_topology(topology));
+
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions.iter().map(|v| v.to_array()).collect::<Vec<_>>(),
+    );
+
+    if !normals.is_empty() {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_NORMAL,
+            normals.iter().map(|v| v.to_array()).collect::<Vec<_>>(),
+        );
+    }
+
+    if !uvs.is_empty() {
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            uvs.iter().map(|v| v.to_array()).collect::<Vec<_>>(),
+        );
+    }
+
+    mesh.set_indices(indices.map(Indices::U32));
+
+    Ok(mesh)
+}
+
+pub(crate) fn create(
+    world: &mut World,
+    module: &str,
+    CreateMesh { data }: CreateMesh,
+) -> Result<AssetHandle, ActionError> {
+    let mesh = build_mesh(data)?;
+    mod_assets::add(world, module, mesh)
+}
+
+/// Replaces mesh data in place, so all entities using the mesh are updated.
+pub(crate) fn update(
+    world: &mut World,
+    UpdateMesh { handle, data }: UpdateMesh,
+) -> Result<(), ActionError> {
+    let mesh = build_mesh(data)?;
+    *mod_assets::get_mut::<Mesh>(world, handle)? = mesh;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Vec2, Vec3};
+
+    use super::*;
+
+    fn quad() -> MeshData {
+        MeshData {
+            topology: MeshTopology::TriangleList,
+            positions: vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y],
+            normals: vec![Vec3::Z; 4],
+            uvs: vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y],
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+        }
+    }
+
+    fn assert_invalid(data: MeshData) {
+        assert!(matches!(build_mesh(data), Err(ActionError::InvalidMesh(_))));
+    }
+
+    #[test]
+    fn valid_mesh() {
+        let mesh = build_mesh(quad()).unwrap();
+
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+        assert_eq!(mesh.count_vertices(), 4);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_some());
+        assert_eq!(mesh.indices().map(Indices::len), Some(6));
+    }
+
+    #[test]
+    fn optional_attributes() {
+        let mesh = build_mesh(MeshData {
+            normals: vec![],
+            uvs: vec![],
+            indices: None,
+            positions: vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+            ..quad()
+        })
+        .unwrap();
+
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_none());
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_none());
+        assert!(mesh.indices().is_none());
+    }
+
+    #[test]
+    fn mismatched_normals() {
+        assert_invalid(MeshData {
+            normals: vec![Vec3::Z; 3],
+            ..quad()
+        });
+    }
+
+    #[test]
+    fn mismatched_uvs() {
+        assert_invalid(MeshData {
+            uvs: vec![Vec2::ZERO; 5],
+            ..quad()
+        });
+    }
+
+    #[test]
+    fn out_of_range_index() {
+        assert_invalid(MeshData {
+            indices: Some(vec![0, 1, 4]),
+            ..quad()
+        });
+    }
+
+    #[test]
+    fn indices_not_fitting_topology() {
+        assert_invalid(MeshData {
+            indices: Some(vec![0, 1, 2, 3]),
+            ..quad()
+        });
+        assert_invalid(MeshData {
+            topology: MeshTopology::LineList,
+            indices: Some(vec![0, 1, 2]),
+            ..quad()
+        });
+        assert_invalid(MeshData {
+            topology: MeshTopology::TriangleStrip,
+            indices: Some(vec![0, 1]),
+            ..quad()
+        });
+    }
+
+    #[test]
+    fn vertices_not_fitting_topology() {
+        assert_invalid(MeshData {
+            indices: None,
+            ..quad()
+        });
+        assert!(build_mesh(MeshData {
+            topology: MeshTopology::TriangleStrip,
+            indices: None,
+            ..quad()
+        })
+        .is_ok());
+    }
+}
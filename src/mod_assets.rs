@@ -0,0 +1,257 @@
+use std::{
+    any::TypeId,
+    path::{Component as PathComponent, Path},
+};
+
+use bevy::{
+    asset::{Asset, HandleId, LoadState},
+    prelude::{
+        App, AssetServer, Assets, Entity as HostEntity, Handle, HandleUntyped, Resource, World,
+    },
+    reflect::Reflect,
+    utils::{get_short_name, HashMap, HashSet},
+};
+use wabi_runtime_api::mod_api::{
+    asset::{AssetHandle, AssetLoadState, GetLoadState, LoadAsset, ReleaseAsset},
+    error::ActionError,
+};
+
+/// Asset type which mods can load and attach to entities.
+#[derive(Debug, Clone, Copy)]
+pub struct ModAssetType {
+    type_id: TypeId,
+    load: fn(&AssetServer, &Path) -> HandleUntyped,
+    insert: fn(&mut World, HostEntity, HandleUntyped),
+    reflect: fn(HandleUntyped) -> Box<dyn Reflect>,
+}
+
+impl ModAssetType {
+    fn new<T: Asset>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            load: |asset_server, path| asset_server.load::<T, _>(path).clone_untyped(),
+            insert: |world, entity, handle| {
+                world.entity_mut(entity).insert(handle.typed::<T>());
+            },
+            reflect: |handle| Box::new(handle.typed::<T>()),
+        }
+    }
+}
+
+/// Asset types available to mods, keyed by type short name.
+#[derive(Resource, Default, Debug)]
+pub struct ModAssetTypes(HashMap<String, ModAssetType>);
+
+pub trait RegisterModAssetType {
+    fn register_mod_asset_type<T: Asset>(&mut self) -> &mut Self;
+}
+
+impl RegisterModAssetType for App {
+    fn register_mod_asset_type<T: Asset>(&mut self) -> &mut Self {
+        self.init_resource::<ModAssetTypes>();
+        self.world.resource_mut::<ModAssetTypes>().0.insert(
+            get_short_name(std::any::type_name::<T>()),
+            ModAssetType::new::<T>(),
+        );
+        self
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ModAsset {
+    pub handle: HandleUntyped,
+    pub asset_type: ModAssetType,
+    /// Modules which holds a reference to this asset.
+    owners: HashSet<String>,
+}
+
+/// Handles kept alive on behalf of mods, which only know their ids.
+#[derive(Resource, Default, Debug)]
+pub struct ModAssets {
+    last_id: u64,
+    assets: HashMap<u64, ModAsset>,
+    ids: HashMap<HandleId, u64>,
+}
+
+impl ModAssets {
+    /// Keeps the handle alive while `module` references it. The same handle always has the same id.
+    pub(crate) fn insert(
+        &mut self,
+        module: &str,
+        asset_type: ModAssetType,
+        handle: HandleUntyped,
+    ) -> AssetHandle {
+        let id = match self.ids.get(&handle.id) {
+            Some(&id) => id,
+            None => {
+                self.last_id += 1;
+                self.ids.insert(handle.id, self.last_id);
+                self.assets.insert(
+                    self.last_id,
+                    ModAsset {
+                        handle,
+                        asset_type,
+                        owners: Default::default(),
+                    },
+                );
+                self.last_id
+            }
+        };
+
+        self.assets
+            .get_mut(&id)
+            .expect("Asset should exists")
+            .owners
+            .insert(module.to_string());
+
+        AssetHandle { id }
+    }
+
+    pub(crate) fn get(&self, handle: AssetHandle) -> Result<&ModAsset, ActionError> {
+        self.assets
+            .get(&handle.id)
+            .ok_or(ActionError::AssetNotFound(handle.id))
+    }
+
+    pub(crate) fn get_typed<T: Asset>(
+        &self,
+        handle: AssetHandle,
+    ) -> Result<Handle<T>, ActionError> {
+        let asset = self.get(handle)?;
+
+        if asset.asset_type.type_id == TypeId::of::<T>() {
+            Ok(asset.handle.clone().typed::<T>())
+        } else {
+            Err(ActionError::MismatchedAssetType {
+                id: handle.id,
+                expected: get_short_name(std::any::type_name::<T>()),
+            })
+        }
+    }
+
+    fn release(&mut self, module: &str, handle: AssetHandle) -> Result<(), ActionError> {
+        let asset = self
+            .assets
+            .get_mut(&handle.id)
+            .ok_or(ActionError::AssetNotFound(handle.id))?;
+
+        asset.owners.remove(module);
+
+        if asset.owners.is_empty() {
+            let asset = self.assets.remove(&handle.id).expect("Asset should exists");
+            self.ids.remove(&asset.handle.id);
+        }
+
+        Ok(())
+    }
+}
+
+fn get_asset_type(world: &World, name: &str) -> Result<ModAssetType, ActionError> {
+    world
+        .get_resource::<ModAssetTypes>()
+        .and_then(|types| types.0.get(name).copied())
+        .ok_or_else(|| ActionError::UnknownAssetType(name.to_string()))
+}
+
+/// Adds an asset created by `module`, like a procedural mesh.
+pub(crate) fn add<T: Asset>(
+    world: &mut World,
+    module: &str,
+    asset: T,
+) -> Result<AssetHandle, ActionError> {
+    let handle = world
+        .get_resource_mut::<Assets<T>>()
+        .ok_or_else(|| ActionError::UnknownAssetType(get_short_name(std::any::type_name::<T>())))?
+        .add(asset);
+
+    Ok(world.resource_mut::<ModAssets>().insert(
+        module,
+        ModAssetType::new::<T>(),
+        handle.clone_untyped(),
+    ))
+}
+
+/// Gets a mutable reference to an asset which mods have a handle to.
+pub(crate) fn get_mut<T: Asset>(
+    world: &mut World,
+    handle: AssetHandle,
+) -> Result<&mut T, ActionError> {
+    let typed = world.resource::<ModAssets>().get_typed::<T>(handle)?;
+
+    world
+        .get_resource_mut::<Assets<T>>()
+        .ok_or_else(|| ActionError::UnknownAssetType(get_short_name(std::any::type_name::<T>())))?
+        .into_inner()
+        .get_mut(&typed)
+        .ok_or(ActionError::AssetNotFound(handle.id))
+}
+
+/// Loads an asset on a path relative to `module_dir`. Paths can't leave module directory.
+pub(crate) fn load(
+    world: &mut World,
+    module: &str,
+    module_dir: &Path,
+    LoadAsset { path, asset_type }: LoadAsset,
+) -> Result<AssetHandle, ActionError> {
+    let is_relative = Path::new(&path)
+        .components()
+        .all(|component| matches!(component, PathComponent::Normal(_) | PathComponent::CurDir));
+
+    if path.is_empty() || !is_relative {
+        return Err(ActionError::InvalidAssetPath(path));
+    }
+
+    let asset_type = get_asset_type(world, &asset_type)?;
+    let handle = (asset_type.load)(world.resource::<AssetServer>(), &module_dir.join(&path));
+
+    Ok(world
+        .resource_mut::<ModAssets>()
+        .insert(module, asset_type, handle))
+}
+
+pub(crate) fn load_state(
+    world: &World,
+    GetLoadState { handle }: GetLoadState,
+) -> Result<AssetLoadState, ActionError> {
+    let id = world.resource::<ModAssets>().get(handle)?.handle.id;
+
+    Ok(match world.resource::<AssetServer>().get_load_state(id) {
+        LoadState::NotLoaded => AssetLoadState::NotLoaded,
+        LoadState::Loading => AssetLoadState::Loading,
+        LoadState::Loaded => AssetLoadState::Loaded,
+        LoadState::Failed => AssetLoadState::Failed,
+        LoadState::Unloaded => AssetLoadState::Unloaded,
+    })
+}
+
+pub(crate) fn release(
+    world: &mut World,
+    module: &str,
+    ReleaseAsset { handle }: ReleaseAsset,
+) -> Result<(), ActionError> {
+    world.resource_mut::<ModAssets>().release(module, handle)
+}
+
+/// Inserts the typed handle of the asset, like `Handle<Mesh>`, on the entity.
+pub(crate) fn insert_handle(
+    world: &mut World,
+    entity: HostEntity,
+    handle: AssetHandle,
+) -> Result<(), ActionError> {
+    let asset = world.resource::<ModAssets>().get(handle)?;
+    let (insert, handle) = (asset.asset_type.insert, asset.handle.clone());
+
+    insert(world, entity, handle);
+
+    Ok(())
+}
+
+/// Typed handle of the asset, like `Handle<Mesh>`, so it can be written on component fields.
+pub(crate) fn reflect_handle(
+    world: &World,
+    handle: AssetHandle,
+) -> Result<Box<dyn Reflect>, ActionError> {
+    let asset = world.resource::<ModAssets>().get(handle)?;
+
+    Ok((asset.asset_type.reflect)(asset.handle.clone()))
+}
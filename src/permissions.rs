@@ -0,0 +1,58 @@
+use bevy::{
+    prelude::{warn, Resource},
+    utils::{HashMap, HashSet},
+};
+use wabi_runtime_api::mod_api::capability::Capability;
+
+/// Host side of mod capabilities. A mod is granted the capabilities requested on its manifest
+/// which are also allowed here, so mods without manifest are granted none, besides those given
+/// on `granted`.
+#[derive(Resource, Debug, Clone)]
+pub struct ModPermissions {
+    /// Capabilities mods are allowed to have. Defaults to all of them.
+    pub allowed: HashSet<Capability>,
+    /// Replaces `allowed` for specific mods, by mod id.
+    pub modules: HashMap<String, HashSet<Capability>>,
+    /// Capabilities given to specific mods, by mod id, even when not requested on their manifest.
+    pub granted: HashMap<String, HashSet<Capability>>,
+}
+
+impl Default for ModPermissions {
+    fn default() -> Self {
+        Self {
+            allowed: Capability::ALL.into_iter().collect(),
+            modules: Default::default(),
+            granted: Default::default(),
+        }
+    }
+}
+
+impl ModPermissions {
+    pub fn allows(&self, module: &str, capability: Capability) -> bool {
+        self.modules
+            .get(module)
+            .unwrap_or(&self.allowed)
+            .contains(&capability)
+    }
+
+    pub fn grants(&self, module: &str, capability: Capability) -> bool {
+        self.granted
+            .get(module)
+            .map_or(false, |granted| granted.contains(&capability))
+    }
+}
+
+/// Parses capabilities requested on a manifest. Unknown names are ignored, since they may come
+/// from a newer SDK.
+pub(crate) fn parse_capabilities(module: &str, names: &[String]) -> HashSet<Capability> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let capability = Capability::from_name(name);
+            if capability.is_none() {
+                warn!("Module {} requested unknown capability {}", module, name);
+            }
+            capability
+        })
+        .collect()
+}
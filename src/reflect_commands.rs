@@ -0,0 +1,360 @@
+use bevy::{
+    prelude::{
+        despawn_with_children_recursive, warn, AppTypeRegistry, BuildWorldChildren,
+        Entity as HostEntity, World,
+    },
+    utils::HashSet,
+};
+use bevy_reflect::{FromReflect, GetPath, Reflect, TypePath};
+use smallvec::SmallVec;
+use wabi_runtime_api::mod_api::{
+    asset::AssetHandle,
+    capability::Capability,
+    commands::{Command, CommandBatch, CommandEntity},
+    ecs::{Component, Entity, Spawn},
+    error::ActionError,
+    query::FieldPath,
+    value::Value,
+};
+
+use crate::{
+    entity_mapping::{to_host_entity, to_mod_entity},
+    mod_assets::{self, ModAssets},
+    reflect_query::{get_component_info, get_reflect_component},
+};
+
+/// Inserts components on the given entity. All components are resolved before inserting any, so
+/// an invalid component doesn't leave behind an incomplete entity.
+fn insert_components(
+    world: &mut World,
+    entity: HostEntity,
+    components: &[Component],
+) -> Result<(), ActionError> {
+    let registry_arc = world.resource::<AppTypeRegistry>().clone();
+    let registry_guard = registry_arc.read();
+
+    // Asset handles sent by mods are replaced by the typed handle, like `Handle<Mesh>`.
+    let (handles, components): (SmallVec<[_; 8]>, SmallVec<[_; 8]>) = components
+        .iter()
+        .partition(|component| component.type_path() == <AssetHandle as TypePath>::type_path());
+
+    let handles = handles
+        .into_iter()
+        .map(|component| {
+            let handle = AssetHandle::from_reflect(component.as_reflect()).unwrap_or_default();
+            world.resource::<ModAssets>().get(handle)?;
+            Ok(handle)
+        })
+        .collect::<Result<SmallVec<[_; 4]>, _>>()?;
+
+    let components = components
+        .into_iter()
+        .map(|component| {
+            let info = get_component_info(world, component.type_path())?;
+            Ok((get_reflect_component(&registry_guard, info)?, component))
+        })
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    for (reflect_component, component) in components {
+        reflect_component.insert(world, entity, component.as_reflect());
+    }
+
+    for handle in handles {
+        mod_assets::insert_handle(world, entity, handle)?;
+    }
+
+    Ok(())
+}
+
+fn spawn_host(
+    world: &mut World,
+    components: &[Component],
+    parent: Option<HostEntity>,
+) -> Result<HostEntity, ActionError> {
+    let entity = world.spawn().id();
+
+    if let Err(err) = insert_components(world, entity, components) {
+        world.despawn(entity);
+        return Err(err);
+    }
+
+    if let Some(parent) = parent {
+        world.entity_mut(parent).push_children(&[entity]);
+    }
+
+    Ok(entity)
+}
+
+pub(crate) fn spawn(world: &mut World, spawn: Spawn) -> Result<Entity, ActionError> {
+    let parent = spawn
+        .parent
+        .map(|parent| to_host_entity(world, parent))
+        .transpose()?;
+
+    spawn_host(world, &spawn.components, parent).map(to_mod_entity)
+}
+
+fn remove_components(
+    world: &mut World,
+    entity: HostEntity,
+    names: &[String],
+) -> Result<(), ActionError> {
+    let registry_arc = world.resource::<AppTypeRegistry>().clone();
+    let registry_guard = registry_arc.read();
+
+    let components = names
+        .iter()
+        .map(|name| {
+            let info = get_component_info(world, name)?;
+            get_reflect_component(&registry_guard, info)
+        })
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    for reflect_component in components {
+        reflect_component.remove(world, entity);
+    }
+
+    Ok(())
+}
+
+fn set_field(
+    world: &mut World,
+    entity: HostEntity,
+    field: &FieldPath,
+    value: &Value,
+) -> Result<(), ActionError> {
+    let registry_arc = world.resource::<AppTypeRegistry>().clone();
+    let registry_guard = registry_arc.read();
+
+    let info = get_component_info(world, &field.component)?;
+    let name = info.name().to_string();
+    let reflect_component = get_reflect_component(&registry_guard, info)?;
+
+    // Asset handles sent by mods are replaced by the typed handle, like on insert.
+    let handle = if value.type_path() == <AssetHandle as TypePath>::type_path() {
+        let handle = AssetHandle::from_reflect(value.as_reflect()).unwrap_or_default();
+        Some(mod_assets::reflect_handle(world, handle)?)
+    } else {
+        None
+    };
+    let expected = handle
+        .as_ref()
+        .map_or_else(|| value.type_path(), |handle| handle.type_path());
+
+    let invalid = |reason: String| ActionError::InvalidPath {
+        component: name.clone(),
+        path: field.path.clone(),
+        reason,
+    };
+
+    let mut component = reflect_component
+        .reflect_mut(world, entity)
+        .ok_or_else(|| ActionError::ComponentNotFound(name.clone()))?;
+
+    let target = component
+        .path_mut(&field.path)
+        .map_err(|err| invalid(err.to_string()))?;
+
+    if target.type_path() != expected {
+        return Err(invalid(format!(
+            "Expected {}, got {}",
+            target.type_path(),
+            expected
+        )));
+    }
+
+    match handle {
+        // Replaced as a whole, so the previous handle is dropped instead of having its id patched.
+        Some(handle) => target
+            .set(handle)
+            .map_err(|_| invalid("Failed to set asset handle".to_string())),
+        None => {
+            target.apply(value.as_reflect());
+            Ok(())
+        }
+    }
+}
+
+fn resolve_entity(
+    world: &World,
+    spawned: &[Option<HostEntity>],
+    entity: CommandEntity,
+) -> Result<HostEntity, ActionError> {
+    match entity {
+        CommandEntity::Entity(entity) => to_host_entity(world, entity),
+        // Entities spawned by the batch may have been despawned by previous commands.
+        CommandEntity::Pending(index) => spawned
+            .get(index as usize)
+            .copied()
+            .flatten()
+            .filter(|&entity| world.entities().contains(entity))
+            .ok_or(ActionError::PendingEntityNotFound(index)),
+    }
+}
+
+fn apply_command(
+    world: &mut World,
+    spawned: &mut Vec<Option<HostEntity>>,
+    command: &Command,
+) -> Result<(), ActionError> {
+    match command {
+        Command::Spawn { components, parent } => {
+            // Pushed even on failure, so following pending entities keep their index.
+            let result = parent
+                .map(|parent| resolve_entity(world, spawned, parent))
+                .transpose()
+                .and_then(|parent| spawn_host(world, components, parent));
+
+            spawned.push(result.as_ref().ok().copied());
+            result.map(|_| ())
+        }
+        Command::Insert { entity, components } => {
+            let entity = resolve_entity(world, spawned, *entity)?;
+            insert_components(world, entity, components)
+        }
+        Command::Remove { entity, components } => {
+            let entity = resolve_entity(world, spawned, *entity)?;
+            remove_components(world, entity, components)
+        }
+        Command::Despawn { entity } => {
+            let entity = resolve_entity(world, spawned, *entity)?;
+            despawn_with_children_recursive(world, entity);
+            Ok(())
+        }
+        Command::Set {
+            entity,
+            field,
+            value,
+        } => {
+            let entity = resolve_entity(world, spawned, *entity)?;
+            set_field(world, entity, field, value)
+        }
+    }
+}
+
+/// Applies commands in order, skipping those which need a capability not `granted`. Failures are
+/// only logged, since the mod has already finished running when its batch is applied.
+pub(crate) fn apply_batch(
+    world: &mut World,
+    module: &str,
+    granted: &HashSet<Capability>,
+    batch: CommandBatch,
+) -> Result<(), ActionError> {
+    let mut spawned = vec![];
+
+    for (index, command) in batch.commands.iter().enumerate() {
+        let capability = command.capability();
+
+        let result = if granted.contains(&capability) {
+            apply_command(world, &mut spawned, command)
+        } else {
+            if let Command::Spawn { .. } = command {
+                spawned.push(None);
+            }
+            Err(ActionError::PermissionDenied(capability))
+        };
+
+        if let Err(err) = result {
+            warn!(
+                "Command {} of module {} failed: {}. Command: {:?}",
+                index, module, err, command
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Children, Parent};
+
+    use super::*;
+
+    fn spawn_command(parent: Option<CommandEntity>) -> Command {
+        Command::Spawn {
+            components: vec![],
+            parent,
+        }
+    }
+
+    fn test_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<AppTypeRegistry>();
+        world
+    }
+
+    #[test]
+    fn pending_entity_referenced_by_later_command() {
+        let mut world = test_world();
+        let mut spawned = vec![];
+
+        apply_command(&mut world, &mut spawned, &spawn_command(None)).unwrap();
+        let parent = CommandEntity::Pending(0);
+        apply_command(&mut world, &mut spawned, &spawn_command(Some(parent))).unwrap();
+
+        let (parent, child) = (spawned[0].unwrap(), spawned[1].unwrap());
+        assert_eq!(world.get::<Parent>(child).map(Parent::get), Some(parent));
+        assert_eq!(&**world.get::<Children>(parent).unwrap(), &[child]);
+    }
+
+    #[test]
+    fn out_of_range_pending_entity() {
+        let mut world = test_world();
+        let mut spawned = vec![];
+
+        apply_command(&mut world, &mut spawned, &spawn_command(None)).unwrap();
+        let result = apply_command(
+            &mut world,
+            &mut spawned,
+            &spawn_command(Some(CommandEntity::Pending(5))),
+        );
+
+        assert!(matches!(result, Err(ActionError::PendingEntityNotFound(5))));
+        // Failed spawns keep their index, so later commands don't target the wrong entity.
+        assert_eq!(spawned.len(), 2);
+        assert_eq!(spawned[1], None);
+        assert_eq!(world.entities().len(), 1);
+    }
+
+    #[test]
+    fn despawned_pending_entity() {
+        let mut world = test_world();
+        let mut spawned = vec![];
+
+        apply_command(&mut world, &mut spawned, &spawn_command(None)).unwrap();
+        let despawn = Command::Despawn {
+            entity: CommandEntity::Pending(0),
+        };
+        apply_command(&mut world, &mut spawned, &despawn).unwrap();
+        let result = apply_command(&mut world, &mut spawned, &despawn);
+
+        assert!(matches!(result, Err(ActionError::PendingEntityNotFound(0))));
+    }
+
+    #[test]
+    fn batch_resolves_pending_entities() {
+        let mut world = test_world();
+        let granted = [Capability::Spawn].into_iter().collect();
+        let batch = CommandBatch {
+            commands: vec![
+                spawn_command(None),
+                spawn_command(Some(CommandEntity::Pending(0))),
+                spawn_command(Some(CommandEntity::Pending(5))),
+            ],
+        };
+
+        apply_batch(&mut world, "module", &granted, batch).unwrap();
+
+        // Last spawn fails, since its parent doesn't exist.
+        assert_eq!(world.entities().len(), 2);
+        let mut children = world.query::<&Children>();
+        assert_eq!(
+            children
+                .iter(&world)
+                .map(|children| children.len())
+                .sum::<usize>(),
+            1
+        );
+    }
+}
@@ -1,24 +1,188 @@
+use std::{borrow::Cow, cmp::Ordering};
+
 use bevy::{
     ecs::component::ComponentInfo,
     prelude::{AppTypeRegistry, ReflectComponent, World},
+    utils::get_short_name,
 };
 
+use bevy_reflect::{GetPath, Reflect, TypeRegistry};
 use smallvec::SmallVec;
 use wabi_runtime_api::mod_api::{
-    ecs::{Component, Entity},
-    query::{Filter, Query, QueryFetch, QueryFetchItem},
+    ecs::Component,
+    error::ActionError,
+    query::{FieldPath, Filter, Predicate, Query, QueryFetch, QueryFetchItem},
+    value::Value,
+};
+
+use crate::{
+    aliases::ComponentAliases,
+    entity_mapping::{to_mod_entity, to_mod_value},
 };
 
-fn get_component_info<'w>(world: &'w World, name: &str) -> &'w ComponentInfo {
-    world
-        .components()
+/// Resolves a component by it's full type name, a registered alias or it's short name, in this
+/// order. When resolving by short name, full names are also reduced to short name, so mods keeps
+/// working when a component is moved to another module.
+pub(crate) fn get_component_info<'w>(
+    world: &'w World,
+    name: &str,
+) -> Result<&'w ComponentInfo, ActionError> {
+    let components = world.components();
+
+    if let Some(info) = components.iter().find(|c| c.name() == name) {
+        return Ok(info);
+    }
+
+    if let Some(full_name) = world
+        .get_resource::<ComponentAliases>()
+        .and_then(|aliases| aliases.get(name))
+    {
+        return components
+            .iter()
+            .find(|c| c.name() == full_name)
+            .ok_or_else(|| ActionError::ComponentNotFound(full_name.to_string()));
+    }
+
+    let short_name = get_short_name(name);
+    let candidates = components
         .iter()
-        .find(|c| c.name() == name)
-        .expect("Should exists")
+        .filter(|c| get_short_name(c.name()) == short_name)
+        .collect::<SmallVec<[_; 4]>>();
+
+    match candidates.as_slice() {
+        [] => Err(ActionError::ComponentNotFound(name.to_string())),
+        [info] => Ok(info),
+        _ => Err(ActionError::AmbiguousComponent {
+            name: name.to_string(),
+            candidates: candidates.iter().map(|c| c.name().to_string()).collect(),
+        }),
+    }
+}
+
+pub(crate) fn get_reflect_component<'r>(
+    registry: &'r TypeRegistry,
+    info: &ComponentInfo,
+) -> Result<&'r ReflectComponent, ActionError> {
+    info.type_id()
+        .and_then(|type_id| registry.get(type_id))
+        .and_then(|registration| registration.data::<ReflectComponent>())
+        .ok_or_else(|| ActionError::ComponentNotReflected(info.name().to_string()))
+}
+
+/// Numeric value of any primitive type. Integers are kept apart from floats, so big integers
+/// aren't rounded when compared.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(v) => v as f64,
+            Number::Float(v) => v,
+        }
+    }
+
+    fn compare(self, other: Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+}
+
+fn as_number(value: &dyn Reflect) -> Option<Number> {
+    macro_rules! downcast {
+        ($variant:ident, $cast:ty, $($ty:ty),*) => {
+            $(
+                if let Some(v) = value.downcast_ref::<$ty>() {
+                    return Some(Number::$variant(*v as $cast));
+                }
+            )*
+        };
+    }
+
+    downcast!(Float, f64, f32, f64);
+    downcast!(Int, i128, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+    None
+}
+
+fn as_str(value: &dyn Reflect) -> Option<&str> {
+    if let Some(s) = value.downcast_ref::<String>() {
+        Some(s.as_str())
+    } else {
+        value
+            .downcast_ref::<Cow<'static, str>>()
+            .map(|s| s.as_ref())
+    }
+}
+
+fn compare(a: &dyn Reflect, b: &dyn Reflect) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (as_number(a), as_number(b)) {
+        a.compare(b)
+    } else if let (Some(a), Some(b)) = (as_str(a), as_str(b)) {
+        Some(a.cmp(b))
+    } else {
+        None
+    }
 }
 
-// TODO: Add error handling, since there is no point in panicking when running commands from wasm modules.
-pub(crate) fn dynamic_query(world: &World, query: Query) -> QueryFetch {
+fn equals(a: &dyn Reflect, b: &dyn Reflect) -> Option<bool> {
+    if let (Some(a), Some(b)) = (as_number(a), as_number(b)) {
+        Some(a.compare(b) == Some(Ordering::Equal))
+    } else if let (Some(a), Some(b)) = (as_str(a), as_str(b)) {
+        Some(a == b)
+    } else {
+        a.reflect_partial_eq(b)
+    }
+}
+
+/// Evaluates the predicate against the given value. Returns [`None`] when the value can't be
+/// compared using the given predicate.
+fn evaluate(value: &dyn Reflect, predicate: &Predicate) -> Option<bool> {
+    let result = match predicate {
+        Predicate::Any => true,
+        Predicate::Eq(other) => equals(value, other.as_reflect())?,
+        Predicate::Ne(other) => !equals(value, other.as_reflect())?,
+        Predicate::Lt(other) => compare(value, other.as_reflect())?.is_lt(),
+        Predicate::Le(other) => compare(value, other.as_reflect())?.is_le(),
+        Predicate::Gt(other) => compare(value, other.as_reflect())?.is_gt(),
+        Predicate::Ge(other) => compare(value, other.as_reflect())?.is_ge(),
+        Predicate::Range { min, max } => {
+            compare(value, min.as_reflect())?.is_ge() && compare(value, max.as_reflect())?.is_lt()
+        }
+        Predicate::StartsWith(s) => as_str(value)?.starts_with(s.as_str()),
+        Predicate::EndsWith(s) => as_str(value)?.ends_with(s.as_str()),
+        Predicate::Contains(s) => as_str(value)?.contains(s.as_str()),
+    };
+
+    Some(result)
+}
+
+fn matches_predicate(
+    component: &dyn Reflect,
+    field: &FieldPath,
+    predicate: &Predicate,
+) -> Result<bool, ActionError> {
+    let value = component
+        .path(&field.path)
+        .map_err(|err| ActionError::InvalidPath {
+            component: field.component.clone(),
+            path: field.path.clone(),
+            reason: err.to_string(),
+        })?;
+
+    evaluate(value, predicate).ok_or_else(|| ActionError::InvalidPredicate {
+        component: field.component.clone(),
+        path: field.path.clone(),
+        reason: format!("Can't evaluate {:?} against {:?}", predicate, value),
+    })
+}
+
+pub(crate) fn dynamic_query(world: &World, query: Query) -> Result<QueryFetch, ActionError> {
     let registry_arc = world.resource::<AppTypeRegistry>();
 
     let with = query
@@ -28,7 +192,7 @@ pub(crate) fn dynamic_query(world: &World, query: Query) -> QueryFetch {
             Filter::With(name) => Some(get_component_info(world, name)),
             _ => None,
         })
-        .collect::<SmallVec<[_; 8]>>();
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
 
     let without = query
         .filters
@@ -37,13 +201,37 @@ pub(crate) fn dynamic_query(world: &World, query: Query) -> QueryFetch {
             Filter::Without(name) => Some(get_component_info(world, name)),
             _ => None,
         })
-        .collect::<SmallVec<[_; 8]>>();
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let predicates = query
+        .filters
+        .iter()
+        .filter_map(|f| match f {
+            Filter::Field { field, predicate } => Some(
+                get_component_info(world, &field.component)
+                    .map(|component| (component, field, predicate)),
+            ),
+            _ => None,
+        })
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
 
     let components = query
         .components
         .iter()
         .map(|name| get_component_info(world, name))
-        .collect::<SmallVec<[_; 8]>>();
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let optional = query
+        .optional
+        .iter()
+        .map(|name| get_component_info(world, name))
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let fields = query
+        .fields
+        .iter()
+        .map(|field| Ok((get_component_info(world, &field.component)?, field)))
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
 
     let entities = world
         .archetypes()
@@ -52,6 +240,8 @@ pub(crate) fn dynamic_query(world: &World, query: Query) -> QueryFetch {
             if with.iter().all(|c| arch.contains(c.id()))
                 && without.iter().all(|c| !arch.contains(c.id()))
                 && components.iter().all(|c| arch.contains(c.id()))
+                && fields.iter().all(|(c, _)| arch.contains(c.id()))
+                && predicates.iter().all(|(c, _, _)| arch.contains(c.id()))
             {
                 Some(arch.entities())
             } else {
@@ -62,28 +252,167 @@ pub(crate) fn dynamic_query(world: &World, query: Query) -> QueryFetch {
 
     let registry_guard = registry_arc.internal.read();
 
-    let items = entities
-        .map(|entity| QueryFetchItem {
-            entity: Entity {
-                id: entity.id(),
-                generation: entity.generation(),
-            },
-            components: components
-                .iter()
-                .map(|component| {
-                    let reflect_component = {
-                        registry_guard
-                            .get(component.type_id().unwrap())
-                            .unwrap()
-                            .data::<ReflectComponent>()
-                            .unwrap()
-                    };
-
-                    Component::from(reflect_component.reflect(world, *entity).unwrap())
-                })
-                .collect::<Vec<_>>(),
+    let reflect_components = components
+        .iter()
+        .map(|component| get_reflect_component(&registry_guard, component))
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let reflect_optional = optional
+        .iter()
+        .map(|component| get_reflect_component(&registry_guard, component))
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let reflect_fields = fields
+        .iter()
+        .map(|(component, field)| Ok((get_reflect_component(&registry_guard, component)?, *field)))
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let reflect_predicates = predicates
+        .iter()
+        .map(|(component, field, predicate)| {
+            Ok((
+                get_reflect_component(&registry_guard, component)?,
+                *field,
+                *predicate,
+            ))
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<SmallVec<[_; 8]>, _>>()?;
+
+    let mut items = vec![];
+
+    'entities: for entity in entities {
+        // Predicates are evaluated before anything else, to avoid reflecting components of
+        // entities which will be discarded anyway.
+        for (reflect_component, field, predicate) in reflect_predicates.iter() {
+            let component = reflect_component.reflect(world, *entity).unwrap();
+
+            if !matches_predicate(component, field, predicate)? {
+                continue 'entities;
+            }
+        }
+
+        let components = reflect_components
+            .iter()
+            .map(|reflect_component| {
+                // Archetype was already checked, so the component must exists.
+                Component::from(to_mod_value(
+                    reflect_component.reflect(world, *entity).unwrap(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let optional = reflect_optional
+            .iter()
+            .map(|reflect_component| {
+                reflect_component
+                    .reflect(world, *entity)
+                    .map(|component| Component::from(to_mod_value(component)))
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = Vec::with_capacity(reflect_fields.len());
+        for (reflect_component, field) in reflect_fields.iter() {
+            let component = reflect_component.reflect(world, *entity).unwrap();
+
+            let value = component
+                .path(&field.path)
+                .map_err(|err| ActionError::InvalidPath {
+                    component: field.component.clone(),
+                    path: field.path.clone(),
+                    reason: err.to_string(),
+                })?;
+
+            values.push(Value::from(to_mod_value(value)));
+        }
+
+        items.push(QueryFetchItem {
+            entity: to_mod_entity(*entity),
+            components,
+            optional,
+            fields: values,
+        });
+    }
+
+    Ok(QueryFetch { items })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval<T: Reflect>(value: T, predicate: Predicate) -> Option<bool> {
+        evaluate(&value, &predicate)
+    }
+
+    #[test]
+    fn large_integers_are_not_rounded() {
+        // Both are rounded to the same f64.
+        assert_eq!(
+            eval(u64::MAX, Predicate::Eq(Value::new(u64::MAX - 1))),
+            Some(false)
+        );
+        assert_eq!(
+            eval(u64::MAX, Predicate::Eq(Value::new(u64::MAX))),
+            Some(true)
+        );
+        assert_eq!(
+            eval(i64::MIN, Predicate::Ne(Value::new(i64::MIN + 1))),
+            Some(true)
+        );
+        assert_eq!(
+            eval(u64::MAX - 1, Predicate::Lt(Value::new(u64::MAX))),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn integers_and_floats() {
+        assert_eq!(eval(2u8, Predicate::Eq(Value::new(2.0f32))), Some(true));
+        assert_eq!(eval(2i32, Predicate::Lt(Value::new(2.5f64))), Some(true));
+        assert_eq!(eval(-1.5f32, Predicate::Ge(Value::new(-1i64))), Some(false));
+    }
+
+    #[test]
+    fn range() {
+        let range = || Predicate::Range {
+            min: Value::new(1u32),
+            max: Value::new(3.0f32),
+        };
+
+        assert_eq!(eval(0i16, range()), Some(false));
+        assert_eq!(eval(1u64, range()), Some(true));
+        assert_eq!(eval(2.9f64, range()), Some(true));
+        assert_eq!(eval(3usize, range()), Some(false));
+    }
+
+    #[test]
+    fn strings() {
+        let name = || "Player One".to_string();
+
+        assert_eq!(eval(name(), Predicate::Eq(Value::new(name()))), Some(true));
+        assert_eq!(
+            eval(name(), Predicate::StartsWith("Player".to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(name(), Predicate::EndsWith("Two".to_string())),
+            Some(false)
+        );
+        assert_eq!(
+            eval(name(), Predicate::Contains("r O".to_string())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(name(), Predicate::Gt(Value::new("Player".to_string()))),
+            Some(true)
+        );
+    }
 
-    QueryFetch { items }
+    #[test]
+    fn incomparable_types() {
+        assert_eq!(eval(1u32, Predicate::Lt(Value::new("2".to_string()))), None);
+        assert_eq!(eval(1u32, Predicate::StartsWith("1".to_string())), None);
+        assert_eq!(eval(true, Predicate::Gt(Value::new(false))), None);
+        assert_eq!(eval(1u32, Predicate::Any), Some(true));
+    }
 }
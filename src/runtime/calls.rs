@@ -0,0 +1,127 @@
+use bevy::prelude::{trace, warn, World};
+use wabi_runtime_api::{
+    mod_api::{call::CallMod, capability::Capability, error::ActionError},
+    WabiInstancePlatform, WabiRuntimePlatform, WABI_EXPORT_PREFIX,
+};
+
+use super::{SharedRuntime, WabiRuntime, RUNNING_CONTEXT};
+
+impl WabiRuntime {
+    /// Handles [`CallMod`] action. The calling module context is kept aside while the called
+    /// module runs, so actions sent by exported functions are processed on their own context.
+    pub(super) fn call_mod(id: u32, len: u32) -> u32 {
+        let (call, caller, granted, shared, world) = RUNNING_CONTEXT.with(|cell| {
+            let context = cell.borrow();
+            (
+                context.read_call(id, len),
+                context.name().to_string(),
+                context.is_granted(Capability::Call),
+                context.shared().clone(),
+                context.world(),
+            )
+        });
+
+        if !granted {
+            warn!(
+                "Module {} isn't allowed to call other modules, missing capability {}",
+                caller,
+                Capability::Call.name()
+            );
+            let err = ActionError::PermissionDenied(Capability::Call);
+            return RUNNING_CONTEXT.with(|cell| cell.borrow().send_call_result(Err(err)));
+        }
+
+        let call = match call {
+            Ok(call) => call,
+            Err(err) => {
+                warn!("Module {} sent an invalid call: {}", caller, err);
+                return RUNNING_CONTEXT.with(|cell| cell.borrow().send_call_result(Err(err)));
+            }
+        };
+
+        trace!(
+            "Module {} is calling {}::{}",
+            caller,
+            call.module,
+            call.function
+        );
+
+        let result = shared.call_export(world, call);
+
+        if let Err(err) = &result {
+            warn!("Module {} failed to call another module: {}", caller, err);
+        }
+
+        RUNNING_CONTEXT.with(|cell| cell.borrow().send_call_result(result))
+    }
+}
+
+impl SharedRuntime {
+    fn call_export(&self, world: &mut World, call: CallMod) -> Result<Vec<u8>, ActionError> {
+        let CallMod {
+            module,
+            function,
+            args,
+        } = call;
+
+        let id = self
+            .modules
+            .get_id(&module)
+            .ok_or_else(|| ActionError::ModuleNotFound(module.clone()))?;
+
+        let mut instance = {
+            let mut platform = self.platform.lock().unwrap();
+
+            if platform.is_loading(id) {
+                return Err(ActionError::ModuleNotFound(module));
+            }
+
+            // Modules which are running are on the call stack, like the caller itself.
+            if platform.get_instance(id).is_none() {
+                return Err(ActionError::ReentrantCall(module));
+            }
+
+            platform.start_running_instance(id)
+        };
+
+        instance.run_alloc();
+        instance.write_buffer(&args);
+
+        let outer = RUNNING_CONTEXT.with(|cell| {
+            let mut context = cell.borrow_mut();
+            let outer = std::mem::take(&mut *context);
+            context.setup(&module, world, &mut instance, self.clone());
+            outer
+        });
+
+        let export = format!("{}{}", WABI_EXPORT_PREFIX, function);
+        let result = instance.call_export(&export, args.len() as u32);
+
+        let panic = RUNNING_CONTEXT.with(|cell| {
+            let mut context = cell.borrow_mut();
+            let panic = context.take_panic();
+            context.teardown();
+            *context = outer;
+            panic
+        });
+
+        let result = match result {
+            None => Err(ActionError::ExportNotFound { module, function }),
+            Some(Ok(len)) => Ok(instance.read_buffer(len).to_vec()),
+            Some(Err(trap)) => Err(ActionError::ExportFailed {
+                function,
+                reason: match panic {
+                    Some(panic) => format!("panicked: {}. Trap: {}", panic, trap),
+                    None => trap,
+                },
+            }),
+        };
+
+        self.platform
+            .lock()
+            .unwrap()
+            .finish_running_instance(id, instance);
+
+        result
+    }
+}
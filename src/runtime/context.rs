@@ -1,22 +1,51 @@
-use bevy::prelude::{debug, error, info, trace, warn, World};
+use std::path::Path;
+
+use bevy::{
+    prelude::{debug, error, info, trace, warn, World},
+    utils::{
+        tracing::{level_filters::LevelFilter, Level},
+        HashSet,
+    },
+};
 use bevy_reflect::{
     erased_serde::__private::serde::de::DeserializeSeed,
     serde::{ReflectSerializer, UntypedReflectDeserializer},
-    FromReflect, Reflect, TypeRegistry,
+    FromReflect, Reflect, TypePath, TypeRegistry,
 };
 use wabi_runtime_api::{
-    mod_api::{log::LogMessage, query::Query, Action},
+    mod_api::{
+        asset::{GetLoadState, LoadAsset, ReleaseAsset},
+        call::CallMod,
+        capability::Capability,
+        commands::CommandBatch,
+        ecs::Spawn,
+        error::ActionError,
+        hierarchy::{GetRelatives, SetParent},
+        image::{CreateImage, UpdateImage},
+        log::{LogLevel, LogMessage, PanicMessage},
+        material::{CreateMaterial, PatchMaterial},
+        mesh::{CreateMesh, UpdateMesh},
+        query::Query,
+        storage::{StorageGet, StorageRemove, StorageSet},
+        system::SystemInfo,
+        Action,
+    },
     WabiInstancePlatform,
 };
 
-use crate::reflect_query;
+use crate::{
+    hierarchy, image, input, material, mesh, mod_assets, reflect_commands, reflect_query, seed,
+    storage,
+};
 
-use super::WabiInstance;
+use super::{ModSystems, SharedRuntime, WabiInstance};
 
 pub(super) struct Context {
+    name: String,
+    panic: Option<PanicMessage>,
     instance: *mut WabiInstance,
     world: *mut World,
-    registry: *const TypeRegistry,
+    shared: Option<SharedRuntime>,
 }
 
 impl Context {
@@ -29,7 +58,7 @@ impl Context {
     }
 
     /// **This function should be called only on a callback from wasm module.**
-    fn world(&self) -> &'static mut World {
+    pub(super) fn world(&self) -> &'static mut World {
         debug_assert!(!self.world.is_null());
 
         // SAFETY: Context only runs after setup and in an exclusive system
@@ -37,32 +66,59 @@ impl Context {
     }
 
     /// **This function should be called only on a callback from wasm module.**
-    fn registry(&self) -> &'static TypeRegistry {
-        debug_assert!(!self.registry.is_null());
+    pub(super) fn shared(&self) -> &SharedRuntime {
+        self.shared.as_ref().expect("Context should be set up")
+    }
 
-        // SAFETY: Context only runs after setup and in an exclusive system
-        unsafe { &*self.registry }
+    fn registry(&self) -> &TypeRegistry {
+        &self.shared().registry
+    }
+
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(super) fn is_granted(&self, capability: Capability) -> bool {
+        self.shared()
+            .modules
+            .is_granted(self.world(), &self.name, capability)
+    }
+
+    fn granted(&self) -> HashSet<Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(|&capability| self.is_granted(capability))
+            .collect()
     }
 
     pub(super) fn setup(
         &mut self,
+        name: &str,
         world: &mut World,
         instance: &mut WabiInstance,
-        registry: &TypeRegistry,
+        shared: SharedRuntime,
     ) {
         debug_assert!(self.instance.is_null());
+        self.name = name.to_string();
         self.instance = instance;
         self.world = world;
-        self.registry = registry;
+        self.shared = Some(shared);
+    }
+
+    /// Takes the panic message sent by the module, if it has panicked.
+    pub(super) fn take_panic(&mut self) -> Option<PanicMessage> {
+        self.panic.take()
     }
 
     pub(super) fn teardown(&mut self) {
-        self.registry = std::ptr::null();
+        self.name.clear();
+        self.panic = None;
         self.instance = std::ptr::null_mut();
         self.world = std::ptr::null_mut();
+        self.shared = None;
     }
 
-    fn deserialize_data(&self, len: u32) -> Box<dyn Reflect> {
+    fn deserialize_data(&self, len: u32) -> Result<Box<dyn Reflect>, ActionError> {
         let buffer = self.instance().read_buffer(len);
 
         let reflect_deserializer = UntypedReflectDeserializer::new(self.registry());
@@ -79,7 +135,9 @@ impl Context {
             }
         };
 
-        reflect_deserializer.deserialize(&mut deserializer).unwrap()
+        reflect_deserializer
+            .deserialize(&mut deserializer)
+            .map_err(|err| ActionError::InvalidRequest(err.to_string()))
     }
 
     fn serialize_data(&self, data: Box<dyn Reflect>) -> Vec<u8> {
@@ -93,7 +151,7 @@ impl Context {
         }
     }
 
-    fn send_response(&self, data: Box<dyn Reflect>) -> u32 {
+    pub(super) fn send_response(&self, data: Box<dyn Reflect>) -> u32 {
         let buffer = self.serialize_data(data);
 
         self.instance().write_buffer(&buffer);
@@ -101,29 +159,179 @@ impl Context {
         buffer.len() as u32
     }
 
-    pub(super) fn process_action(&self, id: u32, len: u32, action: Action) -> u32 {
+    pub(super) fn read_call(&self, id: u32, len: u32) -> Result<CallMod, ActionError> {
         assert_eq!(self.instance().id(), id);
 
-        let data = self.deserialize_data(len);
+        decode(&*self.deserialize_data(len)?)
+    }
+
+    /// Sends the value returned by the called module as is, since it's already serialized.
+    pub(super) fn send_call_result(&self, result: Result<Vec<u8>, ActionError>) -> u32 {
+        match result {
+            Ok(buffer) => {
+                self.instance().write_buffer(&buffer);
+                buffer.len() as u32
+            }
+            Err(err) => self.send_response(Box::new(err)),
+        }
+    }
+
+    pub(super) fn process_action(&mut self, id: u32, len: u32, action: Action) -> u32 {
+        assert_eq!(self.instance().id(), id);
+
+        let data = match self.deserialize_data(len) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(
+                    "Module {} sent an invalid action {:?}: {}",
+                    self.name, action, err
+                );
+                return self.send_response(Box::new(err));
+            }
+        };
 
         if action != Action::LOG {
             trace!("Received action: {:?}, data: {:?}", action, data);
         }
 
+        if let Some(capability) = action.capability() {
+            if !self.is_granted(capability) {
+                warn!(
+                    "Module {} isn't allowed to use action {:?}, missing capability {}",
+                    self.name,
+                    action,
+                    capability.name()
+                );
+                return self.send_response(Box::new(ActionError::PermissionDenied(capability)));
+            }
+        }
+
         let maybe_response = match action {
-            Action::LOG => {
-                let LogMessage { level, message } = LogMessage::from_reflect(&*data).unwrap();
-                match level {
-                    0 => trace!(message),
-                    1 => debug!(message),
-                    2 => info!(message),
-                    3 => warn!(message),
-                    4 => error!(message),
-                    _ => error!("Invalid level received: {}. Message: ({})", level, message),
-                };
+            Action::LOG => Self::respond(
+                action,
+                decode::<LogMessage>(&*data).map(|log| self.log(log)),
+            ),
+            Action::PANIC => {
+                // Logged along with the trap, once the module finishes running.
+                self.panic = PanicMessage::from_reflect(&*data);
                 None
             }
-            Action::QUERY => Some(self.process_query(Query::from_reflect(&*data).unwrap())),
+            Action::QUERY => Self::respond(
+                action,
+                decode::<Query>(&*data)
+                    .and_then(|request| reflect_query::dynamic_query(self.world(), request)),
+            ),
+            Action::SPAWN => Self::respond(
+                action,
+                decode::<Spawn>(&*data)
+                    .and_then(|request| reflect_commands::spawn(self.world(), request)),
+            ),
+            Action::COMMANDS => Self::respond(
+                action,
+                decode::<CommandBatch>(&*data).and_then(|request| {
+                    reflect_commands::apply_batch(
+                        self.world(),
+                        &self.name,
+                        &self.granted(),
+                        request,
+                    )
+                }),
+            ),
+            Action::GET_RELATIVES => Self::respond(
+                action,
+                decode::<GetRelatives>(&*data)
+                    .and_then(|request| hierarchy::get_relatives(self.world(), request)),
+            ),
+            Action::SET_PARENT => Self::respond(
+                action,
+                decode::<SetParent>(&*data)
+                    .and_then(|request| hierarchy::set_parent(self.world(), request)),
+            ),
+            Action::REGISTER_SYSTEM => Self::respond(
+                action,
+                decode::<SystemInfo>(&*data).map(|info| {
+                    info!(
+                        "Module {} registered system {}. Reads: {:?}, writes: {:?}",
+                        self.name, info.name, info.reads, info.writes
+                    );
+                    self.world()
+                        .resource_mut::<ModSystems>()
+                        .insert((self.name.clone(), info.name.clone()), info);
+                }),
+            ),
+            Action::STORAGE_GET => Self::respond(
+                action,
+                decode::<StorageGet>(&*data)
+                    .and_then(|request| storage::get(self.world(), &self.name, request)),
+            ),
+            Action::STORAGE_SET => Self::respond(
+                action,
+                decode::<StorageSet>(&*data)
+                    .and_then(|request| storage::set(self.world(), &self.name, request)),
+            ),
+            Action::STORAGE_REMOVE => Self::respond(
+                action,
+                decode::<StorageRemove>(&*data)
+                    .and_then(|request| storage::remove(self.world(), &self.name, request)),
+            ),
+            Action::INPUT => Self::respond(action, input::get_input(self.world())),
+            Action::LOAD_ASSET => {
+                let module_dir = self
+                    .shared()
+                    .modules
+                    .dir(&self.name)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default();
+
+                Self::respond(
+                    action,
+                    decode::<LoadAsset>(&*data).and_then(|request| {
+                        mod_assets::load(self.world(), &self.name, &module_dir, request)
+                    }),
+                )
+            }
+            Action::GET_LOAD_STATE => Self::respond(
+                action,
+                decode::<GetLoadState>(&*data)
+                    .and_then(|request| mod_assets::load_state(self.world(), request)),
+            ),
+            Action::RELEASE_ASSET => Self::respond(
+                action,
+                decode::<ReleaseAsset>(&*data)
+                    .and_then(|request| mod_assets::release(self.world(), &self.name, request)),
+            ),
+            Action::CREATE_MESH => Self::respond(
+                action,
+                decode::<CreateMesh>(&*data)
+                    .and_then(|request| mesh::create(self.world(), &self.name, request)),
+            ),
+            Action::UPDATE_MESH => Self::respond(
+                action,
+                decode::<UpdateMesh>(&*data)
+                    .and_then(|request| mesh::update(self.world(), request)),
+            ),
+            Action::CREATE_MATERIAL => Self::respond(
+                action,
+                decode::<CreateMaterial>(&*data)
+                    .and_then(|request| material::create(self.world(), &self.name, request)),
+            ),
+            Action::PATCH_MATERIAL => Self::respond(
+                action,
+                decode::<PatchMaterial>(&*data)
+                    .and_then(|request| material::patch(self.world(), request)),
+            ),
+            Action::CREATE_IMAGE => Self::respond(
+                action,
+                decode::<CreateImage>(&*data)
+                    .and_then(|request| image::create(self.world(), &self.name, request)),
+            ),
+            Action::UPDATE_IMAGE => Self::respond(
+                action,
+                decode::<UpdateImage>(&*data)
+                    .and_then(|request| image::update(self.world(), request)),
+            ),
+            Action::RANDOM_SEED => Self::respond(action, seed::get_seed(self.world(), &self.name)),
+            Action::CALL_MOD => unreachable!("Calls are handled by WabiRuntime"),
             //
             Action::TEST => {
                 debug!("Received: {:?}", data);
@@ -143,18 +351,96 @@ impl Context {
         }
     }
 
-    fn process_query(&self, query: Query) -> Box<dyn Reflect> {
-        let result = reflect_query::dynamic_query(self.world(), query);
-        result.clone_value()
+    fn log(&self, log: LogMessage) {
+        let LogMessage {
+            level,
+            message,
+            target,
+            module_path,
+            file,
+            line,
+        } = log;
+
+        let module = self.name.as_str();
+        let target = if target.is_empty() {
+            module.to_string()
+        } else {
+            target
+        };
+        let module_path = module_path.unwrap_or_default();
+        let location = match (file, line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file,
+            _ => String::new(),
+        };
+
+        macro_rules! forward {
+            ($log:ident) => {
+                $log!(
+                    module,
+                    mod_target = %target,
+                    module_path = %module_path,
+                    location = %location,
+                    "{}",
+                    message
+                )
+            };
+        }
+
+        match level {
+            LogLevel::Trace => forward!(trace),
+            LogLevel::Debug => forward!(debug),
+            LogLevel::Info => forward!(info),
+            LogLevel::Warn => forward!(warn),
+            LogLevel::Error => forward!(error),
+        }
+    }
+
+    /// Converts the result of an action into a response. Actions which returns `()` only sends
+    /// a response back when they fail.
+    fn respond<T: Reflect>(
+        action: Action,
+        result: Result<T, ActionError>,
+    ) -> Option<Box<dyn Reflect>> {
+        match result {
+            Ok(value) if value.as_any().is::<()>() => None,
+            Ok(value) => Some(Box::new(value)),
+            Err(err) => {
+                warn!("Failed to process action {:?}: {}", action, err);
+                Some(Box::new(err))
+            }
+        }
     }
 }
 
 impl Default for Context {
     fn default() -> Self {
         Self {
+            name: String::new(),
+            panic: None,
             instance: std::ptr::null_mut(),
             world: std::ptr::null_mut(),
-            registry: std::ptr::null(),
+            shared: None,
         }
     }
 }
+
+/// Decodes data sent by a mod, failing instead of panicking when it isn't of the expected type.
+fn decode<T: FromReflect + TypePath>(data: &dyn Reflect) -> Result<T, ActionError> {
+    T::from_reflect(data).ok_or_else(|| {
+        ActionError::InvalidRequest(format!("expected {}", <T as TypePath>::type_path()))
+    })
+}
+
+/// Most verbose level host logs, sent to mods so they don't send records which are filtered out.
+pub(super) fn max_log_level() -> Option<LogLevel> {
+    LevelFilter::current()
+        .into_level()
+        .map(|level| match level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        })
+}
@@ -0,0 +1,249 @@
+use std::path::PathBuf;
+
+use bevy::utils::{HashMap, HashSet};
+use semver::{Version, VersionReq};
+use wabi_runtime_api::mod_api::manifest::{Dependency, ModManifest, API_VERSION};
+
+use super::WabiError;
+
+/// Module waiting for its dependencies to be loaded.
+pub(super) struct PendingModule {
+    pub manifest: ModManifest,
+    pub version: Version,
+    pub dir: PathBuf,
+    pub buffer: Vec<u8>,
+}
+
+fn parse_version(module: &str, version: &str) -> Result<Version, WabiError> {
+    Version::parse(version).map_err(|err| WabiError::InvalidVersion {
+        module: module.to_string(),
+        version: version.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+fn parse_version_req(module: &str, version_req: &str) -> Result<VersionReq, WabiError> {
+    VersionReq::parse(version_req).map_err(|err| WabiError::InvalidVersion {
+        module: module.to_string(),
+        version: version_req.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+/// Checks if manifest versions are valid and if the module was built against a compatible API.
+/// Returns the module version.
+pub(super) fn validate(manifest: &ModManifest) -> Result<Version, WabiError> {
+    let version = parse_version(&manifest.id, &manifest.version)?;
+
+    for dependency in &manifest.dependencies {
+        parse_version_req(&manifest.id, &dependency.version_req)?;
+    }
+
+    // Mods built against an older minor API version (or patch, on 0.x) can run on newer hosts.
+    let host_api = Version::parse(API_VERSION).expect("API_VERSION should be a valid version");
+    let required_api = parse_version_req(&manifest.id, &format!("^{}", manifest.api_version))?;
+
+    if !required_api.matches(&host_api) {
+        return Err(WabiError::IncompatibleApi {
+            module: manifest.id.clone(),
+            required: manifest.api_version.clone(),
+            host: API_VERSION.to_string(),
+        });
+    }
+
+    Ok(version)
+}
+
+/// Returns the dependencies which aren't loaded yet.
+/// Fails if a loaded dependency doesn't match the required version.
+pub(super) fn missing_dependencies<'a>(
+    manifest: &'a ModManifest,
+    loaded: &HashMap<String, Version>,
+) -> Result<Vec<&'a Dependency>, WabiError> {
+    let mut missing = vec![];
+
+    for dependency in &manifest.dependencies {
+        let version_req = parse_version_req(&manifest.id, &dependency.version_req)?;
+
+        match loaded.get(&dependency.id) {
+            Some(version) if version_req.matches(version) => (),
+            Some(version) => {
+                return Err(WabiError::IncompatibleDependency {
+                    module: manifest.id.clone(),
+                    dependency: dependency.id.clone(),
+                    required: dependency.version_req.clone(),
+                    found: version.to_string(),
+                })
+            }
+            None => missing.push(dependency),
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Finds a dependency cycle among pending modules which goes through `id`.
+/// Returns the modules on the cycle, starting and ending on `id`.
+pub(super) fn find_cycle(
+    id: &str,
+    pending: &HashMap<String, PendingModule>,
+) -> Option<Vec<String>> {
+    fn visit(
+        current: &str,
+        target: &str,
+        pending: &HashMap<String, PendingModule>,
+        visited: &mut HashSet<String>,
+        path: &mut Vec<String>,
+    ) -> bool {
+        let module = match pending.get(current) {
+            Some(module) => module,
+            None => return false,
+        };
+
+        for dependency in &module.manifest.dependencies {
+            if dependency.id == target {
+                path.push(dependency.id.clone());
+                return true;
+            }
+
+            if visited.insert(dependency.id.clone()) {
+                path.push(dependency.id.clone());
+                if visit(&dependency.id, target, pending, visited, path) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+
+        false
+    }
+
+    let mut path = vec![id.to_string()];
+    let mut visited = HashSet::default();
+
+    if visit(id, id, pending, &mut visited, &mut path) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(id: &str, dependencies: &[(&str, &str)]) -> ModManifest {
+        ModManifest {
+            id: id.to_string(),
+            version: "1.0.0".to_string(),
+            api_version: API_VERSION.to_string(),
+            dependencies: dependencies
+                .iter()
+                .map(|(id, version_req)| Dependency {
+                    id: id.to_string(),
+                    version_req: version_req.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn pending(manifests: &[ModManifest]) -> HashMap<String, PendingModule> {
+        manifests
+            .iter()
+            .map(|manifest| {
+                let module = PendingModule {
+                    manifest: manifest.clone(),
+                    version: Version::new(1, 0, 0),
+                    dir: PathBuf::new(),
+                    buffer: vec![],
+                };
+                (manifest.id.clone(), module)
+            })
+            .collect()
+    }
+
+    fn loaded(modules: &[(&str, &str)]) -> HashMap<String, Version> {
+        modules
+            .iter()
+            .map(|(id, version)| (id.to_string(), Version::parse(version).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn satisfied_dependency() {
+        let manifest = manifest("a", &[("b", "^1.2")]);
+        let missing = missing_dependencies(&manifest, &loaded(&[("b", "1.3.0")])).unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let manifest = manifest("a", &[("b", "^2.0")]);
+        let result = missing_dependencies(&manifest, &loaded(&[("b", "1.3.0")]));
+        assert!(matches!(
+            result,
+            Err(WabiError::IncompatibleDependency { dependency, found, .. })
+                if dependency == "b" && found == "1.3.0"
+        ));
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let manifest = manifest("a", &[("b", "^1.0"), ("c", "*")]);
+        let missing = missing_dependencies(&manifest, &loaded(&[("b", "1.0.0")])).unwrap();
+        let ids: Vec<_> = missing.iter().map(|dependency| &dependency.id).collect();
+        assert_eq!(ids, ["c"]);
+    }
+
+    #[test]
+    fn simple_cycle() {
+        let pending = pending(&[
+            manifest("a", &[("b", "*")]),
+            manifest("b", &[("c", "*")]),
+            manifest("c", &[("a", "*")]),
+        ]);
+        assert_eq!(
+            find_cycle("a", &pending),
+            Some(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+                "a".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn no_cycle() {
+        let pending = pending(&[manifest("a", &[("b", "*")]), manifest("b", &[("c", "*")])]);
+        assert_eq!(find_cycle("a", &pending), None);
+    }
+
+    #[test]
+    fn self_dependency() {
+        let pending = pending(&[manifest("a", &[("a", "*")])]);
+        assert_eq!(
+            find_cycle("a", &pending),
+            Some(vec!["a".to_string(), "a".to_string()])
+        );
+    }
+
+    #[test]
+    fn compatible_api_version() {
+        assert_eq!(
+            validate(&manifest("a", &[])).unwrap(),
+            Version::new(1, 0, 0)
+        );
+    }
+
+    #[test]
+    fn incompatible_api_version() {
+        let mut manifest = manifest("a", &[]);
+        manifest.api_version = "99.0.0".to_string();
+        assert!(matches!(
+            validate(&manifest),
+            Err(WabiError::IncompatibleApi { required, .. }) if required == "99.0.0"
+        ));
+    }
+}
@@ -1,16 +1,43 @@
-use std::{cell::RefCell, error::Error, fmt::Display};
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use bevy::{
-    prelude::{error, trace, CoreStage, IntoExclusiveSystem, Plugin, Resource, World},
-    utils::HashMap,
+    prelude::{
+        error, info, trace, warn, CoreStage, Deref, DerefMut, Image, IntoExclusiveSystem, Mesh,
+        Plugin, Resource, StandardMaterial, Time, World,
+    },
+    utils::{HashMap, HashSet},
 };
 use bevy_reflect::TypeRegistry;
+use semver::Version;
 use smallvec::SmallVec;
 use wabi_runtime_api::{
-    mod_api::registry::create_type_registry, WabiInstancePlatform, WabiRuntimePlatform,
+    mod_api::{
+        capability::Capability, log::PanicMessage, registry::create_type_registry,
+        system::SystemInfo, time::TimeInfo, Action,
+    },
+    ModuleOptions, WabiInstancePlatform, WabiRuntimePlatform, WABI_SYSTEM_PREFIX,
+};
+
+use crate::{
+    aliases::ComponentAliases,
+    asset::WasmAsset,
+    mod_assets::{ModAssets, RegisterModAssetType},
+    permissions::{self, ModPermissions},
+    seed::{self, WabiSeed},
+    storage::ModStorage,
 };
 
+use self::dependencies::PendingModule;
+
+mod calls;
 mod context;
+mod dependencies;
 pub mod systems;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -32,6 +59,20 @@ pub(super) struct RuntimePlugin;
 impl Plugin for RuntimePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<WabiRuntime>()
+            .init_resource::<ComponentAliases>()
+            .init_resource::<ModSystems>()
+            .init_resource::<ModHandles>()
+            .init_resource::<ModStorage>()
+            .init_resource::<ModTimestep>()
+            .init_resource::<ModAssets>()
+            .init_resource::<WabiSeed>()
+            .init_resource::<ModPermissions>()
+            .init_resource::<ModWasi>()
+            .init_resource::<ModCache>()
+            .register_mod_asset_type::<Mesh>()
+            .register_mod_asset_type::<Image>()
+            .register_mod_asset_type::<StandardMaterial>()
+            .add_startup_system(seed::log_seed)
             .add_system(systems::run_modules.exclusive_system())
             .add_system_to_stage(CoreStage::PreUpdate, systems::load_wasm_modules);
     }
@@ -40,51 +81,513 @@ impl Plugin for RuntimePlugin {
 #[derive(Debug)]
 pub enum WabiError {
     ModuleNotFound(String),
+    DuplicatedModule(String),
+    InvalidVersion {
+        module: String,
+        version: String,
+        reason: String,
+    },
+    /// Module was built against an API version which isn't compatible with host one.
+    IncompatibleApi {
+        module: String,
+        required: String,
+        host: String,
+    },
+    IncompatibleDependency {
+        module: String,
+        dependency: String,
+        required: String,
+        found: String,
+    },
+    /// `cycle` starts and ends on `module`.
+    DependencyCycle {
+        module: String,
+        cycle: Vec<String>,
+    },
+    /// All modules finished loading, but some dependencies weren't among them.
+    /// Each entry on `missing` is the dependency id followed by the version requirement.
+    MissingDependencies {
+        module: String,
+        missing: Vec<String>,
+    },
+    /// Module trapped while running. `panic` holds the panic message sent by the module, if any.
+    ModuleTrapped {
+        module: String,
+        entry_point: String,
+        trap: String,
+        panic: Option<PanicMessage>,
+    },
 }
 
 impl Display for WabiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             WabiError::ModuleNotFound(name) => write!(f, "Module not found: {}", name),
+            WabiError::DuplicatedModule(name) => {
+                write!(f, "Module {} is already loaded", name)
+            }
+            WabiError::InvalidVersion {
+                module,
+                version,
+                reason,
+            } => write!(
+                f,
+                "Module {} has an invalid version {}: {}",
+                module, version, reason
+            ),
+            WabiError::IncompatibleApi {
+                module,
+                required,
+                host,
+            } => write!(
+                f,
+                "Module {} requires API version {}, but host API version is {}",
+                module, required, host
+            ),
+            WabiError::IncompatibleDependency {
+                module,
+                dependency,
+                required,
+                found,
+            } => write!(
+                f,
+                "Module {} requires {} {}, but version {} is loaded",
+                module, dependency, required, found
+            ),
+            WabiError::DependencyCycle { module, cycle } => write!(
+                f,
+                "Module {} has a dependency cycle: {}",
+                module,
+                cycle.join(" -> ")
+            ),
+            WabiError::MissingDependencies { module, missing } => write!(
+                f,
+                "Module {} requires modules which weren't loaded: {}",
+                module,
+                missing.join(", ")
+            ),
+            WabiError::ModuleTrapped {
+                module,
+                entry_point,
+                trap,
+                panic,
+            } => {
+                write!(f, "Module {} trapped on {}", module, entry_point)?;
+                if let Some(panic) = panic {
+                    write!(f, ", panicked: {}", panic)?;
+                }
+                write!(f, ". Trap: {}", trap)
+            }
         }
     }
 }
 
 impl Error for WabiError {}
 
+/// Systems registered by each module, keyed by module and system name.
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+pub struct ModSystems(HashMap<(String, String), SystemInfo>);
+
+/// Modules loaded by the host. Once all of them finished loading, modules still waiting for
+/// dependencies are discarded. When empty, pending modules wait forever.
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+pub struct ModHandles(pub Vec<Handle<WasmAsset>>);
+
+/// Mods run once per frame by default. When `fixed` is set, mods run on steps of `fixed` seconds,
+/// which may happen zero or more times per frame.
+#[derive(Resource, Debug, Clone)]
+pub struct ModTimestep {
+    pub fixed: Option<f64>,
+    /// Max steps run on a single frame, so a slow frame doesn't make the next ones even slower.
+    pub max_steps: u32,
+}
+
+impl Default for ModTimestep {
+    fn default() -> Self {
+        Self {
+            fixed: None,
+            max_steps: 8,
+        }
+    }
+}
+
+/// WASI context given to mods, on native platforms when built with `wasi` feature.
+/// Mods built for `wasm32-wasi` fail to load without it.
+#[derive(Resource, Debug, Clone)]
+pub struct ModWasi {
+    /// Gives WASI context to all mods.
+    pub all: bool,
+    /// Mods which are given a WASI context, by mod id.
+    pub modules: HashSet<String>,
+    /// Preopens mod directory as read-only, so mods can read their own files.
+    pub preopen_mod_dir: bool,
+    /// Folder where mod directories are, which is Bevy assets folder by default.
+    pub assets_root: PathBuf,
+}
+
+impl Default for ModWasi {
+    fn default() -> Self {
+        Self {
+            all: false,
+            modules: Default::default(),
+            preopen_mod_dir: false,
+            assets_root: PathBuf::from("assets"),
+        }
+    }
+}
+
+impl ModWasi {
+    pub fn is_enabled(&self, module: &str) -> bool {
+        self.all || self.modules.contains(module)
+    }
+
+    fn module_options(
+        &self,
+        module: &str,
+        dir: &Path,
+        cache: &ModCache,
+        seed: &WabiSeed,
+    ) -> ModuleOptions {
+        let wasi = self.is_enabled(module);
+
+        ModuleOptions {
+            wasi,
+            preopen_dir: (wasi && self.preopen_mod_dir).then(|| self.assets_root.join(dir)),
+            cache_dir: cache.dir.clone(),
+            seed: seed.for_module(module),
+        }
+    }
+}
+
+/// Compiled modules are cached on `dir`, so they are only compiled again when changed.
+/// Cached files are trusted, so `dir` must be writable only by the game. Native only.
+///
+/// Disabled by default, since only the game knows a suitable place, like a platform cache dir.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ModCache {
+    pub dir: Option<PathBuf>,
+}
+
+/// Data of loaded modules needed by actions, like their ids and directories.
+#[derive(Default, Clone)]
+pub(super) struct LoadedModules {
+    ids: HashMap<String, u32>,
+    directories: HashMap<String, PathBuf>,
+    /// Capabilities requested by each module. Only those allowed by [`ModPermissions`] are granted.
+    capabilities: HashMap<String, HashSet<Capability>>,
+}
+
+impl LoadedModules {
+    pub(super) fn get_id(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    /// Directory of the module, relative to assets folder.
+    pub(super) fn dir(&self, name: &str) -> Option<&Path> {
+        self.directories.get(name).map(PathBuf::as_path)
+    }
+
+    /// Whether the module requested the capability and host allows it, or host granted it.
+    pub(super) fn is_granted(&self, world: &World, name: &str, capability: Capability) -> bool {
+        let requested = self
+            .capabilities
+            .get(name)
+            .map_or(false, |capabilities| capabilities.contains(&capability));
+        let permissions = world.get_resource::<ModPermissions>();
+
+        (requested && permissions.map_or(true, |permissions| permissions.allows(name, capability)))
+            || permissions.map_or(false, |permissions| permissions.grants(name, capability))
+    }
+}
+
+/// Parts of [`WabiRuntime`] used while a module runs. The running context holds
+/// a copy, so actions and calls never need to borrow the runtime, which is borrowed by the
+/// running module.
+#[derive(Clone)]
+pub(super) struct SharedRuntime {
+    pub platform: Arc<Mutex<Platform>>,
+    pub modules: Arc<LoadedModules>,
+    pub registry: Arc<TypeRegistry>,
+}
+
 #[derive(Resource)]
 pub struct WabiRuntime<P: WabiRuntimePlatform = Platform> {
-    inner: P,
-    instances_name_map: HashMap<String, u32>,
+    /// Only locked to take or return instances, never while a module runs, since running modules
+    /// may call other modules.
+    inner: Arc<Mutex<P>>,
+    modules: Arc<LoadedModules>,
+    /// Loaded modules in the order they must run. Dependencies always come before dependents.
+    load_order: Vec<String>,
+    versions: HashMap<String, Version>,
+    /// Copies of [`ModWasi`], [`ModCache`] and [`WabiSeed`] resources, used when modules are
+    /// instantiated.
+    wasi: ModWasi,
+    cache: ModCache,
+    seed: WabiSeed,
+    pending: HashMap<String, PendingModule>,
     last_id: u32,
-    type_registry: TypeRegistry,
+    type_registry: Arc<TypeRegistry>,
+    /// Sent to modules along with each system invocation.
+    time: TimeInfo,
+    frame_count: u64,
+    fixed_accumulator: f64,
+    fixed_elapsed: f64,
 }
 
 impl WabiRuntime {
     pub fn get_module_id(&self, name: &str) -> Result<u32, WabiError> {
-        self.instances_name_map
-            .get(name)
-            .copied()
+        self.modules
+            .get_id(name)
             .ok_or_else(|| WabiError::ModuleNotFound(name.to_string()))
     }
 
-    pub fn load_module(&mut self, name: &str, buffer: &[u8]) {
-        // TODO: Convert this to a soft error later on
-        assert!(
-            self.get_module_id(name).is_err(),
-            "Can't have two modules with same name"
+    /// Directory of the module, relative to assets folder.
+    pub fn module_dir(&self, name: &str) -> Option<&Path> {
+        self.modules.dir(name)
+    }
+
+    /// Whether the module requested the capability and host allows it, or host granted it.
+    pub fn is_granted(&self, world: &World, name: &str, capability: Capability) -> bool {
+        self.modules.is_granted(world, name, capability)
+    }
+
+    fn shared(&self) -> SharedRuntime {
+        SharedRuntime {
+            platform: self.inner.clone(),
+            modules: self.modules.clone(),
+            registry: self.type_registry.clone(),
+        }
+    }
+
+    /// Loads a module once all dependencies declared on its manifest are loaded.
+    /// Modules without manifest have no dependencies, so are loaded right away.
+    pub fn load_module(&mut self, asset: &WasmAsset) -> Result<(), WabiError> {
+        let name = asset.name.as_str();
+
+        if self.get_module_id(name).is_ok() || self.pending.contains_key(name) {
+            return Err(WabiError::DuplicatedModule(name.to_string()));
+        }
+
+        let manifest = match &asset.manifest {
+            Some(manifest) => manifest.clone(),
+            None => {
+                warn!(
+                    "Module {} has no manifest, so it only has capabilities granted by host",
+                    name
+                );
+                self.instantiate(
+                    name,
+                    Version::new(0, 0, 0),
+                    HashSet::default(),
+                    &asset.dir,
+                    &asset.buffer,
+                );
+                self.load_ready_modules();
+                return Ok(());
+            }
+        };
+
+        let version = dependencies::validate(&manifest)?;
+
+        self.pending.insert(
+            name.to_string(),
+            PendingModule {
+                manifest,
+                version,
+                dir: asset.dir.clone(),
+                buffer: asset.buffer.clone(),
+            },
+        );
+
+        if let Some(cycle) = dependencies::find_cycle(name, &self.pending) {
+            self.pending.remove(name);
+            return Err(WabiError::DependencyCycle {
+                module: name.to_string(),
+                cycle,
+            });
+        }
+
+        self.load_ready_modules();
+
+        if let Some(pending) = self.pending.get(name) {
+            if let Ok(missing) =
+                dependencies::missing_dependencies(&pending.manifest, &self.versions)
+            {
+                let ids = missing
+                    .iter()
+                    .map(|dependency| dependency.id.as_str())
+                    .collect::<Vec<_>>();
+                warn!(
+                    "Module {} is waiting for dependencies: {}",
+                    name,
+                    ids.join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads pending modules which have all dependencies loaded. Modules which depend on
+    /// incompatible versions are discarded.
+    fn load_ready_modules(&mut self) {
+        loop {
+            let mut next = None;
+
+            for (name, pending) in self.pending.iter() {
+                match dependencies::missing_dependencies(&pending.manifest, &self.versions) {
+                    Ok(missing) if missing.is_empty() => {
+                        next = Some((name.clone(), Ok(())));
+                        break;
+                    }
+                    Ok(_) => (),
+                    Err(err) => {
+                        next = Some((name.clone(), Err(err)));
+                        break;
+                    }
+                }
+            }
+
+            let (name, result) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            let pending = self
+                .pending
+                .remove(&name)
+                .expect("Module should be pending");
+
+            match result {
+                Ok(()) => {
+                    let capabilities =
+                        permissions::parse_capabilities(&name, &pending.manifest.capabilities);
+                    self.instantiate(
+                        &name,
+                        pending.version,
+                        capabilities,
+                        &pending.dir,
+                        &pending.buffer,
+                    )
+                }
+                Err(err) => error!("Refusing to load module {}. Error: {}", name, err),
+            }
+        }
+    }
+
+    /// Discards modules still waiting for dependencies. Returns why each one wasn't loaded.
+    pub fn discard_pending(&mut self) -> Vec<WabiError> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|(name, pending)| {
+                match dependencies::missing_dependencies(&pending.manifest, &self.versions) {
+                    Ok(missing) => WabiError::MissingDependencies {
+                        module: name,
+                        missing: missing
+                            .into_iter()
+                            .map(|dependency| {
+                                format!("{} {}", dependency.id, dependency.version_req)
+                            })
+                            .collect(),
+                    },
+                    Err(err) => err,
+                }
+            })
+            .collect()
+    }
+
+    fn instantiate(
+        &mut self,
+        name: &str,
+        version: Version,
+        capabilities: HashSet<Capability>,
+        dir: &Path,
+        buffer: &[u8],
+    ) {
+        info!(
+            "Loading module {} {} with capabilities: {}",
+            name,
+            version,
+            capabilities
+                .iter()
+                .map(|capability| capability.name())
+                .collect::<Vec<_>>()
+                .join(", ")
         );
 
         self.last_id += 1;
-        self.inner.load_module(self.last_id, buffer);
-        self.instances_name_map
-            .insert(name.to_string(), self.last_id);
+        let options = self.wasi.module_options(name, dir, &self.cache, &self.seed);
+        self.inner
+            .lock()
+            .unwrap()
+            .load_module(self.last_id, name, buffer, &options);
+        self.versions.insert(name.to_string(), version);
+
+        // Not shared while modules aren't running, so it isn't cloned.
+        let modules = Arc::make_mut(&mut self.modules);
+        modules.ids.insert(name.to_string(), self.last_id);
+        modules
+            .directories
+            .insert(name.to_string(), dir.to_path_buf());
+        modules.capabilities.insert(name.to_string(), capabilities);
+        self.load_order.push(name.to_string());
     }
 
     pub fn run_all(&mut self, world: &mut World) {
+        self.frame_count += 1;
+
+        let (delta, elapsed) = world.get_resource::<Time>().map_or((0.0, 0.0), |time| {
+            (time.delta_seconds_f64(), time.seconds_since_startup())
+        });
+        let max_log_level = context::max_log_level();
+        let timestep = world
+            .get_resource::<ModTimestep>()
+            .cloned()
+            .unwrap_or_default();
+
+        let step = match timestep.fixed {
+            Some(step) => step,
+            None => {
+                self.time = TimeInfo {
+                    delta_seconds: delta,
+                    elapsed_seconds: elapsed,
+                    frame_count: self.frame_count,
+                    fixed_timestep: false,
+                    max_log_level,
+                };
+                self.run_modules(world);
+                return;
+            }
+        };
+
+        self.fixed_accumulator += delta;
+
+        let mut steps = 0;
+        while self.fixed_accumulator >= step && steps < timestep.max_steps {
+            self.fixed_accumulator -= step;
+            self.fixed_elapsed += step;
+            steps += 1;
+
+            self.time = TimeInfo {
+                delta_seconds: step,
+                elapsed_seconds: self.fixed_elapsed,
+                frame_count: self.frame_count,
+                fixed_timestep: true,
+                max_log_level,
+            };
+            self.run_modules(world);
+        }
+
+        if steps == timestep.max_steps {
+            // Steps which couldn't run in time are dropped.
+            self.fixed_accumulator %= step;
+        }
+    }
+
+    fn run_modules(&mut self, world: &mut World) {
         let modules = self
-            .instances_name_map
-            .keys()
+            .load_order
+            .iter()
             .cloned()
             .collect::<SmallVec<[_; 8]>>();
 
@@ -98,55 +601,85 @@ impl WabiRuntime {
     pub fn run(&mut self, world: &mut World, name: &str) -> Result<(), WabiError> {
         let id = self.get_module_id(name)?;
 
-        if self.inner.is_loading(id) {
-            return Ok(());
-        }
+        // let begin = Instant::now();
+        let mut instance = {
+            let mut platform = self.inner.lock().unwrap();
+            if platform.is_loading(id) {
+                return Ok(());
+            }
+            platform.start_running_instance(id)
+        };
 
         trace!("Running module {}", name);
 
-        // let begin = Instant::now();
-        let mut instance = self.inner.start_running_instance(id);
-
         // TODO: Find a better place for this
         instance.run_alloc();
 
         // let alloc = Instant::now();
-        RUNNING_CONTEXT.with(|cell| {
-            cell.borrow_mut()
-                .setup(world, &mut instance, &self.type_registry)
-        });
+        let shared = self.shared();
+        RUNNING_CONTEXT.with(|cell| cell.borrow_mut().setup(name, world, &mut instance, shared));
 
-        instance.run_main();
+        // Systems run in export order. A trap stops the remaining ones, since module state may be
+        // inconsistent after it.
+        let mut result = Ok(());
+        for system in instance.systems() {
+            let len = RUNNING_CONTEXT
+                .with(|cell| cell.borrow().send_response(Box::new(self.time.clone())));
 
-        // let finished = Instant::now();
-
-        // trace!(
-        //     "alloc: {}us, finished: {}us",
-        //     (alloc - begin).as_micros(),
-        //     (finished - alloc).as_micros()
-        // );
+            if let Err(trap) = instance.run_system(&system, len) {
+                result = Err((system, trap));
+                break;
+            }
+        }
 
-        RUNNING_CONTEXT.with(|cell| {
-            cell.borrow_mut().teardown();
+        let panic = RUNNING_CONTEXT.with(|cell| {
+            let mut context = cell.borrow_mut();
+            let panic = context.take_panic();
+            context.teardown();
+            panic
         });
 
-        self.inner.finish_running_instance(id, instance);
+        self.inner
+            .lock()
+            .unwrap()
+            .finish_running_instance(id, instance);
 
-        Ok(())
+        result.map_err(|(system, trap)| WabiError::ModuleTrapped {
+            module: name.to_string(),
+            entry_point: format!("{}{}", WABI_SYSTEM_PREFIX, system),
+            trap,
+            panic,
+        })
     }
 
     fn process_action(id: u32, len: u32, action: u8) -> u32 {
-        RUNNING_CONTEXT.with(|cell| cell.borrow_mut().process_action(id, len, action.into()))
+        match Action::from(action) {
+            // Calls runs other modules, which need the context, so it can't be borrowed meanwhile.
+            Action::CALL_MOD => Self::call_mod(id, len),
+            action => {
+                RUNNING_CONTEXT.with(|cell| cell.borrow_mut().process_action(id, len, action))
+            }
+        }
     }
 }
 
 impl Default for WabiRuntime {
     fn default() -> Self {
         Self {
-            inner: Platform::new(Self::process_action),
-            instances_name_map: Default::default(),
+            inner: Arc::new(Mutex::new(Platform::new(Self::process_action))),
+            modules: Default::default(),
+            load_order: Default::default(),
+            versions: Default::default(),
+            wasi: Default::default(),
+            cache: Default::default(),
+            seed: Default::default(),
+            pending: Default::default(),
             last_id: 0,
-            type_registry: create_type_registry(),
+            type_registry: Arc::new(create_type_registry()),
+            time: Default::default(),
+            frame_count: 0,
+            fixed_accumulator: 0.0,
+            fixed_elapsed: 0.0,
         }
     }
 }
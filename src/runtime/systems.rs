@@ -1,8 +1,12 @@
-use bevy::prelude::{AssetEvent, Assets, EventReader, Res, ResMut, World};
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::{error, AssetEvent, AssetServer, Assets, EventReader, Local, Res, ResMut, World},
+    utils::HashSet,
+};
 
-use crate::asset::WasmAsset;
+use crate::{asset::WasmAsset, seed::WabiSeed};
 
-use super::WabiRuntime;
+use super::{ModCache, ModHandles, ModWasi, WabiRuntime};
 
 pub(super) fn run_modules(world: &mut World) {
     world.resource_scope::<WabiRuntime, _>(|world, mut runtime| {
@@ -14,11 +18,45 @@ pub(crate) fn load_wasm_modules(
     mut assets_events: EventReader<AssetEvent<WasmAsset>>,
     mut runtime: ResMut<WabiRuntime>,
     wams: Res<Assets<WasmAsset>>,
+    asset_server: Res<AssetServer>,
+    handles: Res<ModHandles>,
+    mut created: Local<HashSet<HandleId>>,
+    wasi: Res<ModWasi>,
+    cache: Res<ModCache>,
+    seed: Res<WabiSeed>,
 ) {
+    if wasi.is_changed() {
+        runtime.wasi = wasi.clone();
+    }
+
+    if cache.is_changed() {
+        runtime.cache = cache.clone();
+    }
+
+    if seed.is_changed() {
+        runtime.seed = *seed;
+    }
+
     for evt in assets_events.iter() {
         if let AssetEvent::Created { handle } = evt {
+            created.insert(handle.id);
             let asset = wams.get(handle).expect("Asset should be loaded");
-            runtime.load_module(&asset.name, &asset.buffer);
+            if let Err(err) = runtime.load_module(asset) {
+                error!("Failed to load module {}. Error: {}", asset.name, err);
+            }
+        }
+    }
+
+    // Load state turns to loaded before the created event is sent, so only the latter tells
+    // the module was handed to the runtime.
+    let finished = !handles.is_empty()
+        && handles.iter().all(|handle| {
+            created.contains(&handle.id) || asset_server.get_load_state(handle) == LoadState::Failed
+        });
+
+    if finished {
+        for err in runtime.discard_pending() {
+            error!("Failed to load module. Error: {}", err);
         }
     }
 }
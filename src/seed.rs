@@ -0,0 +1,104 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+use bevy::prelude::{info, warn, Res, Resource, World};
+use wabi_runtime_api::mod_api::error::ActionError;
+
+/// Environment variable used to replay a previous session, using the seed logged by it.
+const SEED_VAR: &str = "WABI_SEED";
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Seed of the current session, from which each mod seed is derived. Using the same seed and the
+/// same mods makes all mod random numbers be the same.
+///
+/// By default it's read from `WABI_SEED` or a random one is used. It can also be overridden by
+/// inserting this resource.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WabiSeed(pub u64);
+
+impl Default for WabiSeed {
+    fn default() -> Self {
+        match std::env::var(SEED_VAR) {
+            Ok(value) => match value.parse() {
+                Ok(seed) => return Self(seed),
+                Err(err) => warn!("Ignoring invalid {}={}: {}", SEED_VAR, value, err),
+            },
+            Err(std::env::VarError::NotUnicode(_)) => warn!("Ignoring invalid {}", SEED_VAR),
+            Err(std::env::VarError::NotPresent) => (),
+        }
+
+        let mut bytes = [0; 8];
+        match getrandom::getrandom(&mut bytes) {
+            Ok(()) => Self(u64::from_le_bytes(bytes)),
+            Err(err) => {
+                // Hashers are randomly keyed per process, at least on native platforms.
+                warn!(
+                    "Failed to get a random seed, falling back to hasher keys: {}",
+                    err
+                );
+                Self(RandomState::new().build_hasher().finish())
+            }
+        }
+    }
+}
+
+impl WabiSeed {
+    /// Seed of the given mod, so mods don't share the same random numbers.
+    pub fn for_module(&self, module: &str) -> u64 {
+        let hash = module.bytes().fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+
+        splitmix64(self.0 ^ hash)
+    }
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub(crate) fn log_seed(seed: Res<WabiSeed>) {
+    info!(
+        "Mod session seed: {} (set {} to replay it)",
+        seed.0, SEED_VAR
+    );
+}
+
+pub(crate) fn get_seed(world: &World, module: &str) -> Result<u64, ActionError> {
+    Ok(world.resource::<WabiSeed>().for_module(module))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_module_same_seed() {
+        assert_eq!(
+            WabiSeed(42).for_module("module"),
+            WabiSeed(42).for_module("module")
+        );
+    }
+
+    #[test]
+    fn different_modules_different_seeds() {
+        let seed = WabiSeed(42);
+        assert_ne!(seed.for_module("module_a"), seed.for_module("module_b"));
+        assert_ne!(seed.for_module(""), seed.for_module("module_a"));
+    }
+
+    #[test]
+    fn different_sessions_different_seeds() {
+        assert_ne!(
+            WabiSeed(1).for_module("module"),
+            WabiSeed(2).for_module("module")
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::{Resource, World};
+use wabi_runtime_api::mod_api::{
+    error::ActionError,
+    storage::{StorageGet, StorageRemove, StorageSet, StorageValue},
+};
+
+/// Keys are hex encoded, so with the extension, file names fit on the usual 255 bytes limit.
+const MAX_KEY_LEN: usize = 125;
+const VALUE_EXTENSION: &str = "bin";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Persistent storage of mods. Each mod has its own directory inside `root`, named after the
+/// mod id, where each key is stored on its own file.
+///
+/// There is no file system on web, so all storage actions fail there.
+#[derive(Resource, Debug, Clone)]
+pub struct ModStorage {
+    pub root: PathBuf,
+    /// Max size, in bytes, of all values stored by a single mod.
+    pub quota: u64,
+}
+
+impl Default for ModStorage {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("mod_storage"),
+            quota: 1024 * 1024,
+        }
+    }
+}
+
+impl ModStorage {
+    fn module_dir(&self, module: &str) -> PathBuf {
+        // Mod ids are defined by mods, so only safe characters are kept.
+        let name = module
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+
+        self.root.join(name)
+    }
+}
+
+fn storage_error(err: std::io::Error) -> ActionError {
+    ActionError::Storage(err.to_string())
+}
+
+/// Keys are hex encoded, so they can't escape mod directory nor clash with file system rules.
+fn value_path(dir: &Path, key: &str) -> Result<PathBuf, ActionError> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(ActionError::InvalidStorageKey(key.to_string()));
+    }
+
+    let name = key
+        .bytes()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    Ok(dir.join(name).with_extension(VALUE_EXTENSION))
+}
+
+fn file_size(path: &Path) -> Result<u64, ActionError> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(storage_error(err)),
+    }
+}
+
+fn used_space(dir: &Path) -> Result<u64, ActionError> {
+    let mut used = 0;
+
+    for entry in fs::read_dir(dir).map_err(storage_error)? {
+        let path = entry.map_err(storage_error)?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some(VALUE_EXTENSION) {
+            used += file_size(&path)?;
+        }
+    }
+
+    Ok(used)
+}
+
+pub(crate) fn get(
+    world: &World,
+    module: &str,
+    StorageGet { key }: StorageGet,
+) -> Result<StorageValue, ActionError> {
+    let storage = world.resource::<ModStorage>();
+    let path = value_path(&storage.module_dir(module), &key)?;
+
+    match fs::read(path) {
+        Ok(value) => Ok(StorageValue { value: Some(value) }),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(StorageValue { value: None }),
+        Err(err) => Err(storage_error(err)),
+    }
+}
+
+/// Makes renames on `dir` durable. Only done on Unix, since directories can't be opened as
+/// files elsewhere.
+fn sync_dir(dir: &Path) -> Result<(), ActionError> {
+    #[cfg(unix)]
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(storage_error)?;
+    #[cfg(not(unix))]
+    let _ = dir;
+
+    Ok(())
+}
+
+/// Writes the value on a temporary file first, which replaces the previous one only when fully
+/// written, so a crash never leaves a partially written value.
+pub(crate) fn set(
+    world: &World,
+    module: &str,
+    StorageSet { key, value }: StorageSet,
+) -> Result<(), ActionError> {
+    let storage = world.resource::<ModStorage>();
+    let dir = storage.module_dir(module);
+    let path = value_path(&dir, &key)?;
+
+    fs::create_dir_all(&dir).map_err(storage_error)?;
+
+    // Value being replaced doesn't count, since it's removed by the rename.
+    let size = used_space(&dir)?.saturating_sub(file_size(&path)?) + value.len() as u64;
+    if size > storage.quota {
+        return Err(ActionError::StorageQuotaExceeded {
+            size,
+            quota: storage.quota,
+        });
+    }
+
+    let temp_path = path.with_extension(TEMP_EXTENSION);
+    let mut file = File::create(&temp_path).map_err(storage_error)?;
+    file.write_all(&value).map_err(storage_error)?;
+    file.sync_all().map_err(storage_error)?;
+    drop(file);
+
+    fs::rename(&temp_path, &path).map_err(storage_error)?;
+    sync_dir(&dir)
+}
+
+pub(crate) fn remove(
+    world: &World,
+    module: &str,
+    StorageRemove { key }: StorageRemove,
+) -> Result<(), ActionError> {
+    let storage = world.resource::<ModStorage>();
+    let path = value_path(&storage.module_dir(module), &key)?;
+
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(storage_error(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Storage on its own temporary directory, removed when dropped.
+    struct TestStorage {
+        world: World,
+        root: PathBuf,
+    }
+
+    impl TestStorage {
+        fn new(name: &str, quota: u64) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("wabi_storage_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+
+            let mut world = World::new();
+            world.insert_resource(ModStorage {
+                root: root.clone(),
+                quota,
+            });
+
+            Self { world, root }
+        }
+
+        fn set(&self, key: &str, value: &[u8]) -> Result<(), ActionError> {
+            set(
+                &self.world,
+                "module",
+                StorageSet {
+                    key: key.to_string(),
+                    value: value.to_vec(),
+                },
+            )
+        }
+
+        fn get(&self, key: &str) -> Option<Vec<u8>> {
+            get(
+                &self.world,
+                "module",
+                StorageGet {
+                    key: key.to_string(),
+                },
+            )
+            .unwrap()
+            .value
+        }
+    }
+
+    impl Drop for TestStorage {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn quota_exceeded() {
+        let storage = TestStorage::new("quota_exceeded", 10);
+
+        storage.set("a", &[0; 6]).unwrap();
+        let result = storage.set("b", &[0; 6]);
+
+        assert!(matches!(
+            result,
+            Err(ActionError::StorageQuotaExceeded {
+                size: 12,
+                quota: 10
+            })
+        ));
+        assert_eq!(storage.get("b"), None);
+    }
+
+    #[test]
+    fn overwrite_within_quota() {
+        let storage = TestStorage::new("overwrite_within_quota", 10);
+
+        storage.set("a", &[1; 8]).unwrap();
+        storage.set("a", &[2; 10]).unwrap();
+
+        assert_eq!(storage.get("a"), Some(vec![2; 10]));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let storage = TestStorage::new("invalid_keys", 10);
+
+        assert!(matches!(
+            storage.set("", &[]),
+            Err(ActionError::InvalidStorageKey(_))
+        ));
+        assert!(matches!(
+            storage.set(&"k".repeat(MAX_KEY_LEN + 1), &[]),
+            Err(ActionError::InvalidStorageKey(_))
+        ));
+        storage.set(&"k".repeat(MAX_KEY_LEN), &[]).unwrap();
+    }
+}